@@ -0,0 +1,312 @@
+use crate::command::{Command, InfoSection};
+use crate::pattern;
+use crate::tracking::TrackingMode;
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Users allowed to connect to the server. Authentication is only required once at
+/// least one user is defined.
+#[derive(Debug, Default)]
+pub struct Acl {
+    users: HashMap<String, Arc<User>>,
+}
+
+/// A user defined in the config file as `user=<name> <sha256 of password> <rules>...`.
+#[derive(Debug)]
+pub struct User {
+    name: String,
+    password_hash: [u8; 32],
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Rule {
+    AllowAllCommands,
+    DenyAllCommands,
+    AllowCommand(String),
+    DenyCommand(String),
+    AllowKeyspaces(Bytes),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum AclError {
+    #[error("missing password hash for user '{0}'")]
+    MissingPasswordHash(String),
+
+    #[error("invalid password hash for user '{0}', expected a hex encoded sha256")]
+    InvalidPasswordHash(String),
+
+    #[error("invalid rule '{0}' for user '{1}'")]
+    InvalidRule(String, String),
+
+    #[error("user '{0}' is defined more than once")]
+    DuplicateUser(String),
+}
+
+impl Acl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_user(&mut self, user: User) -> Result<(), AclError> {
+        if self.users.contains_key(&user.name) {
+            return Err(AclError::DuplicateUser(user.name));
+        }
+        self.users.insert(user.name.clone(), Arc::new(user));
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.users.is_empty()
+    }
+
+    /// Returns the user if the password matches.
+    pub fn authenticate(&self, name: &str, password: &[u8]) -> Option<Arc<User>> {
+        let user = self.users.get(name)?;
+        let hash = Sha256::digest(password);
+        // compares every byte so the time taken does not depend on the first mismatch
+        let diff = hash
+            .iter()
+            .zip(user.password_hash.iter())
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        if diff == 0 {
+            Some(user.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl User {
+    /// Parses the value of a `user` directive.
+    pub fn parse(value: &str) -> Result<User, AclError> {
+        let mut tokens = value.split_whitespace();
+        let name = tokens.next().unwrap_or_default().to_string();
+        let hash = tokens
+            .next()
+            .ok_or_else(|| AclError::MissingPasswordHash(name.clone()))?;
+        let password_hash =
+            decode_hash(hash).ok_or_else(|| AclError::InvalidPasswordHash(name.clone()))?;
+
+        let rules = tokens
+            .map(|token| {
+                parse_rule(token)
+                    .ok_or_else(|| AclError::InvalidRule(token.to_string(), name.clone()))
+            })
+            .collect::<Result<Vec<Rule>, AclError>>()?;
+
+        Ok(User {
+            name,
+            password_hash,
+            rules,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks that the user may run the command against the keyspace it targets, later
+    /// command rules take precedence over earlier ones. Commands that see or affect
+    /// every keyspace, like `MONITOR`, need access to all of them.
+    pub fn is_allowed(&self, command: &Command) -> bool {
+        let name = command.name();
+        let mut allowed = false;
+        for rule in &self.rules {
+            match rule {
+                Rule::AllowAllCommands => allowed = true,
+                Rule::DenyAllCommands => allowed = false,
+                Rule::AllowCommand(command) if command == name => allowed = true,
+                Rule::DenyCommand(command) if command == name => allowed = false,
+                _ => {}
+            }
+        }
+        if !allowed {
+            return false;
+        }
+
+        if spans_every_keyspace(command) {
+            return self
+                .rules
+                .iter()
+                .any(|rule| matches!(rule, Rule::AllowKeyspaces(pattern) if pattern == "*"));
+        }
+        target_keyspaces(command).iter().all(|keyspace| {
+            self.rules.iter().any(|rule| match rule {
+                Rule::AllowKeyspaces(pattern) => pattern::matches(pattern, keyspace),
+                _ => false,
+            })
+        })
+    }
+}
+
+// commands that expose the keys, commands or clients of every keyspace, or act on
+// the connections of other users
+fn spans_every_keyspace(command: &Command) -> bool {
+    match command {
+        Command::Info(cmd) => matches!(cmd.section(), None | Some(InfoSection::Keyspaces)),
+        Command::Tracking(cmd) => matches!(cmd.mode(), Some(TrackingMode::Broadcast(_))),
+        Command::Monitor
+        | Command::SlowLogGet(_)
+        | Command::MemoryStats
+        | Command::Keyspaces
+        | Command::ClientKill(_)
+        | Command::ClientPause(_) => true,
+        _ => false,
+    }
+}
+
+fn parse_rule(token: &str) -> Option<Rule> {
+    if let Some(pattern) = token.strip_prefix('~') {
+        return Some(Rule::AllowKeyspaces(Bytes::from(pattern.to_string())));
+    }
+    let token = token.to_lowercase();
+    match token.as_str() {
+        "+@all" => Some(Rule::AllowAllCommands),
+        "-@all" => Some(Rule::DenyAllCommands),
+        _ => {
+            if let Some(command) = token.strip_prefix('+').filter(|c| !c.is_empty()) {
+                Some(Rule::AllowCommand(command.to_string()))
+            } else {
+                token
+                    .strip_prefix('-')
+                    .filter(|c| !c.is_empty())
+                    .map(|command| Rule::DenyCommand(command.to_string()))
+            }
+        }
+    }
+}
+
+fn decode_hash(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut hash = [0; 32];
+    for (idx, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16).ok()?;
+    }
+    Some(hash)
+}
+
+// keyspaces a command reads or changes, including the keyspaces that are created,
+// altered, renamed, copied, dropped or watched
+fn target_keyspaces(command: &Command) -> Vec<Bytes> {
+    match command {
+        Command::Create(cmd) => vec![cmd.keyspace()],
+        Command::Alter(cmd) => vec![cmd.keyspace()],
+        Command::RenameKeyspace(cmd) => vec![cmd.source(), cmd.destination()],
+        Command::CopyKeyspace(cmd) => vec![cmd.source(), cmd.destination()],
+        Command::Drop(cmd) => vec![cmd.keyspace()],
+        Command::WatchEvents(cmd) => vec![cmd.keyspace()],
+        Command::UnwatchEvents(cmd) => vec![cmd.keyspace()],
+        command => command.keyspace().into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::cmd as command;
+
+    // sha256 of "secret"
+    const SECRET_HASH: &str = "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b";
+
+    #[test]
+    fn authenticate_given_wrong_password_returns_none() {
+        let mut acl = Acl::new();
+        acl.add_user(User::parse(&format!("alice {}", SECRET_HASH)).unwrap())
+            .unwrap();
+
+        assert!(acl.is_enabled());
+        assert!(acl.authenticate("alice", b"secret").is_some());
+        assert!(acl.authenticate("alice", b"guess").is_none());
+        assert!(acl.authenticate("bob", b"secret").is_none());
+    }
+
+    #[test]
+    fn is_allowed_given_command_and_keyspace_rules_checks_both() {
+        let user = User::parse(&format!("alice {} +@all -drop ~cache:*", SECRET_HASH)).unwrap();
+
+        assert!(user.is_allowed(&command(&["get", "cache:users", "foo"])));
+        assert!(user.is_allowed(&command(&["create", "cache:orders"])));
+        assert!(!user.is_allowed(&command(&["get", "users", "foo"])));
+        assert!(!user.is_allowed(&command(&["drop", "cache:users"])));
+    }
+
+    #[test]
+    fn is_allowed_given_rename_checks_both_keyspaces() {
+        let user = User::parse(&format!("alice {} +@all ~cache:*", SECRET_HASH)).unwrap();
+
+        assert!(user.is_allowed(&command(&[
+            "rename",
+            "keyspace",
+            "cache:next",
+            "to",
+            "cache:users"
+        ])));
+        assert!(!user.is_allowed(&command(&[
+            "rename",
+            "keyspace",
+            "cache:next",
+            "to",
+            "users"
+        ])));
+        assert!(!user.is_allowed(&command(&[
+            "copy",
+            "keyspace",
+            "users",
+            "to",
+            "cache:users"
+        ])));
+    }
+
+    #[test]
+    fn is_allowed_given_monitor_requires_every_keyspace() {
+        let admin = User::parse(&format!("alice {} +@all ~*", SECRET_HASH)).unwrap();
+        let restricted = User::parse(&format!("bob {} +@all ~cache:*", SECRET_HASH)).unwrap();
+
+        assert!(admin.is_allowed(&command(&["monitor"])));
+        assert!(!restricted.is_allowed(&command(&["monitor"])));
+    }
+
+    #[test]
+    fn is_allowed_given_commands_spanning_keyspaces_requires_every_keyspace() {
+        let admin = User::parse(&format!("alice {} +@all ~*", SECRET_HASH)).unwrap();
+        let restricted = User::parse(&format!("bob {} +@all ~cache:*", SECRET_HASH)).unwrap();
+
+        for args in [
+            &["slowlog", "get"][..],
+            &["tracking", "on", "bcast"],
+            &["memory", "stats"],
+            &["info"],
+            &["info", "keyspaces"],
+            &["keyspaces"],
+            &["client", "kill", "1"],
+            &["client", "pause", "100"],
+        ] {
+            assert!(admin.is_allowed(&command(args)), "{:?}", args);
+            assert!(!restricted.is_allowed(&command(args)), "{:?}", args);
+        }
+        assert!(restricted.is_allowed(&command(&["tracking", "on"])));
+        assert!(restricted.is_allowed(&command(&["info", "memory"])));
+    }
+
+    #[test]
+    fn parse_given_invalid_entries_returns_error() {
+        assert_eq!(
+            User::parse("alice").unwrap_err(),
+            AclError::MissingPasswordHash("alice".to_string())
+        );
+        assert_eq!(
+            User::parse("alice abc").unwrap_err(),
+            AclError::InvalidPasswordHash("alice".to_string())
+        );
+        assert_eq!(
+            User::parse(&format!("alice {} get", SECRET_HASH)).unwrap_err(),
+            AclError::InvalidRule("get".to_string(), "alice".to_string())
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use bytes::BytesMut;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+// bits are addressed from the most significant bit of the first byte,
+// so offset 0 is the leftmost bit of the value
+fn mask(offset: u64) -> u8 {
+    1 << (7 - (offset % 8))
+}
+
+pub fn get_bit(data: &[u8], offset: u64) -> bool {
+    match data.get((offset / 8) as usize) {
+        Some(byte) => byte & mask(offset) != 0,
+        None => false,
+    }
+}
+
+/// Sets the bit at offset, growing the buffer with zero bytes if required.
+/// Returns the previous value of the bit.
+pub fn set_bit(data: &mut BytesMut, offset: u64, value: bool) -> bool {
+    let idx = (offset / 8) as usize;
+    if idx >= data.len() {
+        data.resize(idx + 1, 0);
+    }
+    let previous = data[idx] & mask(offset) != 0;
+    if value {
+        data[idx] |= mask(offset);
+    } else {
+        data[idx] &= !mask(offset);
+    }
+    previous
+}
+
+/// Resolves an inclusive byte range where negative indexes count from the end.
+/// Returns None if the range is empty.
+pub fn resolve_range(len: usize, start: i64, end: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let end = if end < 0 { len + end } else { end.min(len - 1) };
+    if len == 0 || start > end || start >= len {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}
+
+pub fn count(data: &[u8]) -> u64 {
+    data.iter().map(|byte| byte.count_ones() as u64).sum()
+}
+
+/// Returns the position of the first bit set to `bit` in `data`, positions are
+/// relative to the start of the value and not the start of `data`.
+pub fn position(data: &[u8], bit: bool, byte_offset: usize) -> Option<u64> {
+    for (idx, byte) in data.iter().enumerate() {
+        let byte = if bit { *byte } else { !*byte };
+        if byte != 0 {
+            let position = (byte_offset + idx) as u64 * 8 + byte.leading_zeros() as u64;
+            return Some(position);
+        }
+    }
+    None
+}
+
+/// Applies the operation across all sources, shorter sources are zero padded
+/// to the length of the longest one.
+pub fn operate(operation: BitOperation, sources: &[&[u8]]) -> BytesMut {
+    let len = sources.iter().map(|source| source.len()).max().unwrap_or(0);
+    let mut result = BytesMut::zeroed(len);
+    for idx in 0..len {
+        let mut bytes = sources
+            .iter()
+            .map(|source| source.get(idx).copied().unwrap_or(0));
+        let first = bytes.next().unwrap_or(0);
+        result[idx] = match operation {
+            BitOperation::And => bytes.fold(first, |acc, byte| acc & byte),
+            BitOperation::Or => bytes.fold(first, |acc, byte| acc | byte),
+            BitOperation::Xor => bytes.fold(first, |acc, byte| acc ^ byte),
+            BitOperation::Not => !first,
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_bit_given_offset_past_end_grows_buffer() {
+        let mut data = BytesMut::new();
+        assert!(!set_bit(&mut data, 9, true));
+        assert_eq!(&data[..], &[0b0000_0000, 0b0100_0000]);
+        assert!(get_bit(&data, 9));
+        assert!(!get_bit(&data, 100));
+    }
+
+    #[test]
+    fn set_bit_given_set_bit_returns_previous_value() {
+        let mut data = BytesMut::from(&[0b1000_0000][..]);
+        assert!(set_bit(&mut data, 0, false));
+        assert_eq!(&data[..], &[0]);
+    }
+
+    #[test]
+    fn resolve_range_given_negative_indexes_returns_range_from_end() {
+        assert_eq!(resolve_range(10, -3, -1), Some((7, 9)));
+        assert_eq!(resolve_range(10, 0, 100), Some((0, 9)));
+        assert_eq!(resolve_range(10, 5, 2), None);
+        assert_eq!(resolve_range(0, 0, -1), None);
+    }
+
+    #[test]
+    fn position_given_clear_bit_returns_first_clear_bit() {
+        assert_eq!(position(&[0xff, 0xf0], false, 0), Some(12));
+        assert_eq!(position(&[0x00, 0x01], true, 2), Some(31));
+        assert_eq!(position(&[0x00], true, 0), None);
+    }
+
+    #[test]
+    fn operate_given_sources_of_different_length_pads_with_zeroes() {
+        let result = operate(BitOperation::Or, &[&[0b1010], &[0b0101, 0xff]]);
+        assert_eq!(&result[..], &[0b1111, 0xff]);
+        let result = operate(BitOperation::And, &[&[0xff], &[0x0f, 0xff]]);
+        assert_eq!(&result[..], &[0x0f, 0x00]);
+        let result = operate(BitOperation::Not, &[&[0x0f]]);
+        assert_eq!(&result[..], &[0xf0]);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::hash::murmur64a;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::f64::consts::LN_2;
+use thiserror::Error;
+
+const MAGIC: &[u8] = b"BLOM";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + 8 + 8 + 8 + 8;
+pub const DEFAULT_CAPACITY: u64 = 100;
+pub const DEFAULT_ERROR_RATE: f64 = 0.01;
+/// Largest filter that can be reserved, the same 512MB bound as bitmaps.
+pub const MAX_BITS: u64 = 1 << 32;
+// every hash is computed while the keyspace is locked, more hashes than this only
+// matter for error rates far below anything useful
+const MAX_HASHES: u32 = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    capacity: u64,
+    error_rate: f64,
+    len: u64,
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid bloom filter encoding")]
+pub struct DecodeError;
+
+impl BloomFilter {
+    /// Creates a filter sized to hold `capacity` items with a false positive
+    /// rate of `error_rate`, the caller is responsible for validating both and
+    /// for checking that the filter is at most `MAX_BITS` large.
+    pub fn new(capacity: u64, error_rate: f64) -> Self {
+        let num_bits = num_bits(capacity, error_rate);
+        let num_hashes = ((num_bits as f64 / capacity as f64) * LN_2)
+            .round()
+            .clamp(1.0, MAX_HASHES as f64) as u32;
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+            capacity,
+            error_rate,
+            len: 0,
+        }
+    }
+
+    // positions are derived using double hashing, see Kirsch and Mitzenmacher,
+    // "Less Hashing, Same Performance: Building a Better Bloom Filter"
+    fn positions(&self, item: &[u8]) -> impl Iterator<Item = u64> {
+        let h1 = murmur64a(item, 0);
+        let h2 = murmur64a(item, h1);
+        let num_bits = self.num_bits;
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    /// Adds an item, returns false if the item may have been added before.
+    pub fn add(&mut self, item: &[u8]) -> bool {
+        let mut added = false;
+        for position in self.positions(item) {
+            let (word, bit) = ((position / 64) as usize, position % 64);
+            if self.bits[word] & (1 << bit) == 0 {
+                self.bits[word] |= 1 << bit;
+                added = true;
+            }
+        }
+        if added {
+            self.len += 1;
+        }
+        added
+    }
+
+    pub fn contains(&self, item: &[u8]) -> bool {
+        self.positions(item).all(|position| {
+            let (word, bit) = ((position / 64) as usize, position % 64);
+            self.bits[word] & (1 << bit) != 0
+        })
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.bits.capacity() * std::mem::size_of::<u64>()
+    }
+
+    /// Encodes the filter as a magic header, version and the filter parameters
+    /// in little endian followed by the bit array.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(HEADER_LEN + self.bits.len() * 8);
+        buf.put_slice(MAGIC);
+        buf.put_u8(VERSION);
+        buf.put_u32_le(self.num_hashes);
+        buf.put_u64_le(self.num_bits);
+        buf.put_u64_le(self.capacity);
+        buf.put_f64_le(self.error_rate);
+        buf.put_u64_le(self.len);
+        for word in &self.bits {
+            buf.put_u64_le(*word);
+        }
+        buf.freeze()
+    }
+
+    pub fn from_bytes(mut data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError);
+        }
+        data.advance(MAGIC.len());
+        if data.get_u8() != VERSION {
+            return Err(DecodeError);
+        }
+
+        let num_hashes = data.get_u32_le();
+        let num_bits = data.get_u64_le();
+        let capacity = data.get_u64_le();
+        let error_rate = data.get_f64_le();
+        let len = data.get_u64_le();
+        if num_hashes == 0
+            || num_hashes > MAX_HASHES
+            || num_bits == 0
+            || num_bits > MAX_BITS
+            || data.remaining() as u64 != num_bits.div_ceil(64) * 8
+        {
+            return Err(DecodeError);
+        }
+
+        let mut bits = Vec::with_capacity(data.remaining() / 8);
+        while data.has_remaining() {
+            bits.push(data.get_u64_le());
+        }
+        Ok(BloomFilter {
+            bits,
+            num_bits,
+            num_hashes,
+            capacity,
+            error_rate,
+            len,
+        })
+    }
+}
+
+/// Number of bits of a filter holding `capacity` items with a false positive rate of
+/// `error_rate`.
+pub fn num_bits(capacity: u64, error_rate: f64) -> u64 {
+    let num_bits = ((-(capacity as f64) * error_rate.ln()) / (LN_2 * LN_2)).ceil() as u64;
+    num_bits.max(64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_given_added_item_returns_true() {
+        let mut filter = BloomFilter::new(100, 0.01);
+        assert!(filter.add(b"foo"));
+        assert!(filter.contains(b"foo"));
+        assert!(!filter.add(b"foo"));
+        assert_eq!(filter.len, 1);
+    }
+
+    #[test]
+    fn contains_given_missing_items_returns_false_within_error_rate() {
+        let mut filter = BloomFilter::new(1000, 0.01);
+        for i in 0..1000 {
+            filter.add(format!("item:{}", i).as_bytes());
+        }
+        let false_positives = (1000..11000)
+            .filter(|i| filter.contains(format!("item:{}", i).as_bytes()))
+            .count();
+        assert!(false_positives < 200);
+    }
+
+    #[test]
+    fn from_bytes_given_encoded_filter_returns_same_filter() {
+        let mut filter = BloomFilter::new(100, 0.001);
+        filter.add(b"foo");
+        filter.add(b"bar");
+        assert_eq!(BloomFilter::from_bytes(&filter.to_bytes()), Ok(filter));
+    }
+
+    #[test]
+    fn from_bytes_given_truncated_data_returns_decode_error() {
+        let filter = BloomFilter::new(100, 0.01);
+        let encoded = filter.to_bytes();
+        assert_eq!(
+            BloomFilter::from_bytes(&encoded[..encoded.len() - 1]),
+            Err(DecodeError)
+        );
+    }
+
+    #[test]
+    fn from_bytes_given_crafted_header_returns_decode_error() {
+        let filter = BloomFilter::new(100, 0.01);
+        let mut encoded = filter.to_bytes().to_vec();
+        let num_hashes_at = MAGIC.len() + 1;
+        encoded[num_hashes_at..num_hashes_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(BloomFilter::from_bytes(&encoded), Err(DecodeError));
+
+        encoded[num_hashes_at..num_hashes_at + 4].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(BloomFilter::from_bytes(&encoded), Err(DecodeError));
+
+        let mut encoded = filter.to_bytes().to_vec();
+        let num_bits_at = num_hashes_at + 4;
+        encoded[num_bits_at..num_bits_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(BloomFilter::from_bytes(&encoded), Err(DecodeError));
+    }
+
+    #[test]
+    fn new_given_tiny_error_rate_caps_hashes() {
+        let filter = BloomFilter::new(100, 1e-300);
+        assert_eq!(filter.num_hashes, MAX_HASHES);
+    }
+}
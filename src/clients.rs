@@ -0,0 +1,190 @@
+use crate::frame::Frame;
+use crate::pubsub::Subscriber;
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+/// Connections currently open on the server, used by the `CLIENT` commands.
+#[derive(Debug, Default)]
+pub struct Clients {
+    clients: RwLock<HashMap<u64, Arc<Client>>>,
+    paused_until: Mutex<Option<Instant>>,
+}
+
+/// A connection as seen by other connections. The id is the id of the connection's
+/// subscriber so that a single id identifies the connection everywhere.
+#[derive(Debug)]
+pub struct Client {
+    subscriber: Subscriber,
+    addr: String,
+    created: Instant,
+    name: Mutex<Option<Bytes>>,
+    // milliseconds since the client was created
+    last_active: AtomicU64,
+    last_command: Mutex<&'static str>,
+    input_buffer: AtomicUsize,
+    killed: Notify,
+}
+
+impl Clients {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, subscriber: Subscriber, addr: String) -> Arc<Client> {
+        let client = Arc::new(Client {
+            subscriber,
+            addr,
+            created: Instant::now(),
+            name: Mutex::new(None),
+            last_active: AtomicU64::new(0),
+            last_command: Mutex::new(""),
+            input_buffer: AtomicUsize::new(0),
+            killed: Notify::new(),
+        });
+        self.clients.write().insert(client.id(), client.clone());
+        client
+    }
+
+    pub fn unregister(&self, id: u64) {
+        self.clients.write().remove(&id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.read().len()
+    }
+
+    /// Returns the clients ordered by id.
+    pub fn list(&self) -> Vec<Arc<Client>> {
+        let mut clients: Vec<Arc<Client>> = self.clients.read().values().cloned().collect();
+        clients.sort_by_key(|client| client.id());
+        clients
+    }
+
+    pub fn kill_by_id(&self, id: u64) -> bool {
+        match self.clients.read().get(&id) {
+            Some(client) => {
+                client.kill();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn kill_by_addr(&self, addr: &str) -> bool {
+        let mut killed = false;
+        for client in self.clients.read().values() {
+            if client.addr == addr {
+                client.kill();
+                killed = true;
+            }
+        }
+        killed
+    }
+
+    /// Holds off command execution on every connection for the duration, pausing
+    /// again replaces the previous deadline.
+    pub fn pause(&self, duration: Duration) {
+        *self.paused_until.lock() = Some(Instant::now() + duration);
+    }
+
+    pub fn paused_until(&self) -> Option<Instant> {
+        let mut paused_until = self.paused_until.lock();
+        match *paused_until {
+            Some(until) if until > Instant::now() => Some(until),
+            Some(_) => {
+                *paused_until = None;
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+impl Client {
+    pub fn id(&self) -> u64 {
+        self.subscriber.id()
+    }
+
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub fn set_name(&self, name: Bytes) {
+        *self.name.lock() = Some(name);
+    }
+
+    /// Records a command run by the client along with the size of its input buffer.
+    pub fn record_command(&self, command: &'static str, input_buffer: usize) {
+        let elapsed = self.created.elapsed().as_millis() as u64;
+        self.last_active.store(elapsed, Ordering::Relaxed);
+        *self.last_command.lock() = command;
+        self.input_buffer.store(input_buffer, Ordering::Relaxed);
+    }
+
+    pub fn kill(&self) {
+        self.killed.notify_one();
+    }
+
+    /// Completes once the client has been killed by another connection.
+    pub async fn killed(&self) {
+        self.killed.notified().await
+    }
+
+    pub fn to_frame(&self) -> Frame {
+        let age = self.created.elapsed();
+        let last_active = Duration::from_millis(self.last_active.load(Ordering::Relaxed));
+        let name = self.name.lock().clone().unwrap_or_default();
+        Frame::Map(vec![
+            Frame::String(Bytes::from_static(b"id")),
+            Frame::Integer(self.id() as i64),
+            Frame::String(Bytes::from_static(b"addr")),
+            Frame::String(Bytes::from(self.addr.clone())),
+            Frame::String(Bytes::from_static(b"name")),
+            Frame::String(name),
+            Frame::String(Bytes::from_static(b"age")),
+            Frame::Integer(age.as_secs() as i64),
+            Frame::String(Bytes::from_static(b"idle")),
+            Frame::Integer(age.saturating_sub(last_active).as_secs() as i64),
+            Frame::String(Bytes::from_static(b"last_command")),
+            Frame::String(Bytes::from_static(self.last_command.lock().as_bytes())),
+            Frame::String(Bytes::from_static(b"input_buffer")),
+            Frame::Integer(self.input_buffer.load(Ordering::Relaxed) as i64),
+            Frame::String(Bytes::from_static(b"pending_pushes")),
+            Frame::Integer(self.subscriber.pending() as i64),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_by_addr_given_unknown_addr_returns_false() {
+        let clients = Clients::new();
+        let (subscriber, _rx) = Subscriber::new(8);
+        let client = clients.register(subscriber, "127.0.0.1:5000".to_string());
+
+        assert!(!clients.kill_by_addr("127.0.0.1:5001"));
+        assert!(clients.kill_by_addr("127.0.0.1:5000"));
+        assert!(clients.kill_by_id(client.id()));
+        clients.unregister(client.id());
+        assert!(!clients.kill_by_id(client.id()));
+        assert!(clients.list().is_empty());
+    }
+
+    #[tokio::test]
+    async fn pause_given_elapsed_deadline_is_not_paused() {
+        let clients = Clients::new();
+        clients.pause(Duration::from_secs(60));
+        assert!(clients.paused_until().is_some());
+        clients.pause(Duration::ZERO);
+        assert!(clients.paused_until().is_none());
+    }
+}
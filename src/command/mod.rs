@@ -1,6 +1,11 @@
+use crate::bitmap::BitOperation;
+use crate::bloom;
 use crate::db::Evictor;
 use crate::frame::Frame;
+use crate::json::Path;
+use crate::tracking::TrackingMode;
 use bytes::Bytes;
+use serde_json::Number;
 use std::iter::Peekable;
 use std::ops::Add;
 use std::str::{self, Utf8Error};
@@ -16,33 +21,80 @@ struct Parser {
     tokens: Peekable<IntoIter<Frame>>,
 }
 
+/// A limit or default of a keyspace, given on `CREATE` or changed with `ALTER`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyspaceOption {
+    MaxKeys(u64),
+    MaxMemory(u64),
+    DefaultExpireAfter(u64),
+    MaxValueSize(u64),
+    ReadOnly(bool),
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Create {
     keyspace: Bytes,
     evictor: Evictor,
+    options: Vec<KeyspaceOption>,
     if_not_exists: bool,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Alter {
+    keyspace: Bytes,
+    evictor: Option<Evictor>,
+    options: Vec<KeyspaceOption>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RenameKeyspace {
+    source: Bytes,
+    destination: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CopyKeyspace {
+    source: Bytes,
+    destination: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Truncate {
+    keyspace: Bytes,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Set {
     keyspace: Bytes,
     key: Bytes,
     value: Bytes,
-    expire_at: Option<u64>,
+    expiry: Option<Expiry>,
     if_not_exists: bool,
     if_exists: bool,
+    if_version: Option<u64>,
+    if_value_equals: Option<Bytes>,
+}
+
+/// Expiry of a `SET`, an expiry relative to now is only turned into a timestamp once
+/// the command runs so that a queued command is not shortened by the wait.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expiry {
+    At(u64),
+    After(u64),
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Get {
     keyspace: Bytes,
     key: Bytes,
+    with_version: bool,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Del {
     keyspace: Bytes,
     key: Bytes,
+    if_value_equals: Option<Bytes>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -62,17 +114,307 @@ pub struct Ttl {
     key: Bytes,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct JsonSet {
+    keyspace: Bytes,
+    key: Bytes,
+    path: Path,
+    value: serde_json::Value,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonGet {
+    keyspace: Bytes,
+    key: Bytes,
+    path: Path,
+    native: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonDel {
+    keyspace: Bytes,
+    key: Bytes,
+    path: Path,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonIncrBy {
+    keyspace: Bytes,
+    key: Bytes,
+    path: Path,
+    by: Number,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PfAdd {
+    keyspace: Bytes,
+    key: Bytes,
+    elements: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PfCount {
+    keyspace: Bytes,
+    keys: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PfMerge {
+    keyspace: Bytes,
+    destination: Bytes,
+    sources: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BfReserve {
+    keyspace: Bytes,
+    key: Bytes,
+    capacity: u64,
+    error_rate: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BfAdd {
+    keyspace: Bytes,
+    key: Bytes,
+    items: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BfExists {
+    keyspace: Bytes,
+    key: Bytes,
+    items: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetBit {
+    keyspace: Bytes,
+    key: Bytes,
+    offset: u64,
+    value: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GetBit {
+    keyspace: Bytes,
+    key: Bytes,
+    offset: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BitCount {
+    keyspace: Bytes,
+    key: Bytes,
+    range: Option<(i64, i64)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BitPos {
+    keyspace: Bytes,
+    key: Bytes,
+    bit: bool,
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BitOp {
+    keyspace: Bytes,
+    operation: BitOperation,
+    destination: Bytes,
+    keys: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Append {
+    keyspace: Bytes,
+    key: Bytes,
+    value: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GetRange {
+    keyspace: Bytes,
+    key: Bytes,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetRange {
+    keyspace: Bytes,
+    key: Bytes,
+    offset: u64,
+    value: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StrLen {
+    keyspace: Bytes,
+    key: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Subscribe {
+    channels: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Unsubscribe {
+    channels: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Publish {
+    channel: Bytes,
+    message: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WatchEvents {
+    keyspace: Bytes,
+    pattern: Option<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnwatchEvents {
+    keyspace: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MGet {
+    keyspace: Bytes,
+    keys: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Tracking {
+    mode: Option<TrackingMode>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Auth {
+    username: String,
+    password: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ClientKill {
+    Id(u64),
+    Addr(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClientSetName {
+    name: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClientPause {
+    duration: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MemoryUsage {
+    keyspace: Bytes,
+    key: Bytes,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BigKeys {
+    keyspace: Bytes,
+    limit: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct HotKeys {
+    keyspace: Bytes,
+    limit: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SlowLogGet {
+    count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InfoSection {
+    Server,
+    Clients,
+    Stats,
+    Memory,
+    Keyspaces,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Info {
+    section: Option<InfoSection>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Command {
     Create(Create),
+    Alter(Alter),
+    RenameKeyspace(RenameKeyspace),
+    CopyKeyspace(CopyKeyspace),
+    Truncate(Truncate),
     Set(Set),
     Get(Get),
     Del(Del),
     Drop(Drop),
     Count(Count),
     Ttl(Ttl),
+    JsonSet(JsonSet),
+    JsonGet(JsonGet),
+    JsonDel(JsonDel),
+    JsonIncrBy(JsonIncrBy),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    BfReserve(BfReserve),
+    BfAdd(BfAdd),
+    BfMAdd(BfAdd),
+    BfExists(BfExists),
+    BfMExists(BfExists),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitPos(BitPos),
+    BitOp(BitOp),
+    Append(Append),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    StrLen(StrLen),
     Ping,
     Keyspaces,
+    Multi,
+    Exec,
+    Discard,
+    Subscribe(Subscribe),
+    PSubscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    PUnsubscribe(Unsubscribe),
+    Publish(Publish),
+    WatchEvents(WatchEvents),
+    UnwatchEvents(UnwatchEvents),
+    MGet(MGet),
+    Tracking(Tracking),
+    Auth(Auth),
+    ClientList,
+    ClientInfo,
+    ClientKill(ClientKill),
+    ClientSetName(ClientSetName),
+    ClientPause(ClientPause),
+    Info(Info),
+    SlowLogGet(SlowLogGet),
+    SlowLogLen,
+    SlowLogReset,
+    Monitor,
+    MemoryUsage(MemoryUsage),
+    MemoryStats,
+    BigKeys(BigKeys),
+    HotKeys(HotKeys),
 }
 
 #[derive(Debug, Error)]
@@ -97,6 +439,9 @@ pub enum ParseCommandError {
 
     #[error("unknown command '{0}'")]
     UnknownCommand(String),
+
+    #[error("invalid json value for '{0}' command")]
+    InvalidJson(String),
 }
 
 impl Parser {
@@ -140,6 +485,193 @@ impl Parser {
             _ => Err(ParseCommandError::InvalidFormat),
         }
     }
+
+    pub fn next_as_integer<T: std::str::FromStr>(
+        &mut self,
+        arg: &str,
+        command: &str,
+    ) -> Result<Option<T>, ParseCommandError> {
+        let value = match self.next_as_string()? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        value.parse::<T>().map(Some).map_err(|_| {
+            ParseCommandError::InvalidArgValue(value, arg.to_string(), command.to_string())
+        })
+    }
+
+    pub fn next_as_bit(&mut self, command: &str) -> Result<Option<bool>, ParseCommandError> {
+        let value = match self.next_as_string()? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        match value.as_str() {
+            "0" => Ok(Some(false)),
+            "1" => Ok(Some(true)),
+            _ => Err(ParseCommandError::InvalidArgValue(
+                value,
+                "bit".to_string(),
+                command.to_string(),
+            )),
+        }
+    }
+
+    /// Parses the `EQUALS <VALUE>` part of an `IF VALUE EQUALS <VALUE>` condition.
+    pub fn next_as_expected_value(&mut self, command: &str) -> Result<Bytes, ParseCommandError> {
+        let equals_token = self
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?
+            .to_lowercase();
+        if !matches!(equals_token.as_str(), "equals") {
+            return Err(ParseCommandError::InvalidArg(
+                equals_token,
+                command.to_string(),
+            ));
+        }
+        self.next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))
+    }
+
+    pub fn next_as_evictor(&mut self, command: &str) -> Result<Evictor, ParseCommandError> {
+        let value = self
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?
+            .to_lowercase();
+        match value.as_str() {
+            "nop" => Ok(Evictor::Nop),
+            "random" => Ok(Evictor::Random),
+            "lru" => Ok(Evictor::Lru),
+            _ => Err(ParseCommandError::InvalidArgValue(
+                value,
+                "evictor".to_string(),
+                command.to_string(),
+            )),
+        }
+    }
+
+    /// Parses a size in bytes, optionally suffixed with a `kb`, `mb` or `gb` unit.
+    pub fn next_as_size(&mut self, arg: &str, command: &str) -> Result<u64, ParseCommandError> {
+        let value = self
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?;
+
+        let value_lowercase = value.to_lowercase();
+        let units = [
+            ("kb", 1024),
+            ("mb", 1024 * 1024),
+            ("gb", 1024 * 1024 * 1024),
+        ];
+        let (number, multiplier) = units
+            .iter()
+            .find_map(|(unit, multiplier)| {
+                value_lowercase
+                    .strip_suffix(unit)
+                    .map(|number| (number, *multiplier))
+            })
+            .unwrap_or((value_lowercase.as_str(), 1));
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .ok_or_else(|| {
+                ParseCommandError::InvalidArgValue(value, arg.to_string(), command.to_string())
+            })
+    }
+
+    /// Parses the keyspace option starting with `token`, returns `None` if the token
+    /// does not start one.
+    pub fn next_as_keyspace_option(
+        &mut self,
+        token: &str,
+        command: &str,
+    ) -> Result<Option<KeyspaceOption>, ParseCommandError> {
+        let option = match token {
+            "max" => {
+                let limit = self
+                    .next_as_string()?
+                    .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?
+                    .to_lowercase();
+                match limit.as_str() {
+                    "keys" => KeyspaceOption::MaxKeys(
+                        self.next_as_integer("max keys", command)?
+                            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?,
+                    ),
+                    "memory" => {
+                        KeyspaceOption::MaxMemory(self.next_as_size("max memory", command)?)
+                    }
+                    "value" => {
+                        self.next_as_token("size", command)?;
+                        KeyspaceOption::MaxValueSize(self.next_as_size("max value size", command)?)
+                    }
+                    _ => return Err(ParseCommandError::InvalidArg(limit, command.to_string())),
+                }
+            }
+            "default" => {
+                self.next_as_token("expire", command)?;
+                self.next_as_token("after", command)?;
+                KeyspaceOption::DefaultExpireAfter(
+                    self.next_as_integer("default expire after", command)?
+                        .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?,
+                )
+            }
+            "readonly" => KeyspaceOption::ReadOnly(true),
+            "readwrite" => KeyspaceOption::ReadOnly(false),
+            _ => return Ok(None),
+        };
+        Ok(Some(option))
+    }
+
+    /// Parses the `KEYSPACE <SOURCE> TO <DESTINATION>` arguments of a command.
+    pub fn next_as_keyspace_pair(
+        &mut self,
+        command: &str,
+    ) -> Result<(Bytes, Bytes), ParseCommandError> {
+        self.next_as_token("keyspace", command)?;
+        let source = self
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?;
+        self.next_as_token("to", command)?;
+        let destination = self
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?;
+        if self.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount(command.to_string()));
+        }
+        Ok((source, destination))
+    }
+
+    // consumes the next token which must be the expected keyword
+    fn next_as_token(&mut self, expected: &str, command: &str) -> Result<(), ParseCommandError> {
+        let token = self
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?
+            .to_lowercase();
+        if token != expected {
+            return Err(ParseCommandError::InvalidArg(token, command.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn remaining_as_bytes(&mut self) -> Result<Vec<Bytes>, ParseCommandError> {
+        let mut values = Vec::new();
+        while let Some(value) = self.next_as_bytes()? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    pub fn next_as_json_path(&mut self, command: &str) -> Result<Option<Path>, ParseCommandError> {
+        let path = match self.next_as_string()? {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        Path::parse(&path).map(Some).map_err(|_| {
+            ParseCommandError::InvalidArgValue(path, "path".to_string(), command.to_string())
+        })
+    }
 }
 
 impl Create {
@@ -151,6 +683,7 @@ impl Create {
         let mut command = Create {
             keyspace,
             evictor: Evictor::Nop,
+            options: Vec::new(),
             if_not_exists: false,
         };
 
@@ -165,22 +698,7 @@ impl Create {
                 .to_lowercase();
 
             if matches!(token.as_str(), "evictor") {
-                let value = parser
-                    .next_as_string()?
-                    .ok_or_else(|| ParseCommandError::WrongArgCount("create".to_string()))?
-                    .to_lowercase();
-                match value.as_str() {
-                    "nop" => command.evictor = Evictor::Nop,
-                    "random" => command.evictor = Evictor::Random,
-                    "lru" => command.evictor = Evictor::Lru,
-                    _ => {
-                        return Err(ParseCommandError::InvalidArgValue(
-                            value,
-                            token,
-                            "create".to_string(),
-                        ))
-                    }
-                }
+                command.evictor = parser.next_as_evictor("create")?;
             } else if matches!(token.as_str(), "if") {
                 let not_token = parser
                     .next_as_string()?
@@ -210,6 +728,8 @@ impl Create {
                 } else {
                     return Err(ParseCommandError::InvalidFormat);
                 }
+            } else if let Some(option) = parser.next_as_keyspace_option(&token, "create")? {
+                command.options.push(option);
             } else {
                 return Err(ParseCommandError::InvalidArg(token, "create".to_string()));
             }
@@ -224,43 +744,141 @@ impl Create {
     pub fn evictor(&self) -> Evictor {
         self.evictor
     }
+    pub fn options(&self) -> &[KeyspaceOption] {
+        &self.options
+    }
     pub fn if_not_exists(&self) -> bool {
         self.if_not_exists
     }
 }
 
-impl Set {
+impl Alter {
     fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
         let keyspace = parser
             .next_as_bytes()?
-            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
-
-        let key = parser
-            .next_as_bytes()?
-            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
-
-        let value = parser
-            .next_as_bytes()?
-            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
+            .ok_or_else(|| ParseCommandError::WrongArgCount("alter".to_string()))?;
 
-        let mut command = Set {
+        let mut command = Alter {
             keyspace,
-            key,
-            value,
-            expire_at: None,
-            if_not_exists: false,
-            if_exists: false,
+            evictor: None,
+            options: Vec::new(),
         };
-
-        if !parser.has_remaining() {
-            return Ok(command);
+        while let Some(token) = parser.next_as_string()? {
+            let token = token.to_lowercase();
+            if matches!(token.as_str(), "evictor") {
+                command.evictor = Some(parser.next_as_evictor("alter")?);
+            } else if let Some(option) = parser.next_as_keyspace_option(&token, "alter")? {
+                command.options.push(option);
+            } else {
+                return Err(ParseCommandError::InvalidArg(token, "alter".to_string()));
+            }
+        }
+        if command.evictor.is_none() && command.options.is_empty() {
+            return Err(ParseCommandError::WrongArgCount("alter".to_string()));
         }
 
-        while parser.has_remaining() {
-            let token = parser
-                .next_as_string()?
-                .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?
-                .to_lowercase();
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn evictor(&self) -> Option<Evictor> {
+        self.evictor
+    }
+
+    pub fn options(&self) -> &[KeyspaceOption] {
+        &self.options
+    }
+}
+
+impl RenameKeyspace {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let (source, destination) = parser.next_as_keyspace_pair("rename")?;
+        Ok(RenameKeyspace {
+            source,
+            destination,
+        })
+    }
+
+    pub fn source(&self) -> Bytes {
+        self.source.clone()
+    }
+
+    pub fn destination(&self) -> Bytes {
+        self.destination.clone()
+    }
+}
+
+impl CopyKeyspace {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let (source, destination) = parser.next_as_keyspace_pair("copy")?;
+        Ok(CopyKeyspace {
+            source,
+            destination,
+        })
+    }
+
+    pub fn source(&self) -> Bytes {
+        self.source.clone()
+    }
+
+    pub fn destination(&self) -> Bytes {
+        self.destination.clone()
+    }
+}
+
+impl Truncate {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("truncate".to_string()))?;
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("truncate".to_string()));
+        }
+        Ok(Truncate { keyspace })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+}
+
+impl Set {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
+
+        let value = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
+
+        let mut command = Set {
+            keyspace,
+            key,
+            value,
+            expiry: None,
+            if_not_exists: false,
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None,
+        };
+
+        if !parser.has_remaining() {
+            return Ok(command);
+        }
+
+        while parser.has_remaining() {
+            let token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?
+                .to_lowercase();
 
             if matches!(token.as_str(), "expire") {
                 let at_or_after_token = parser
@@ -274,9 +892,9 @@ impl Set {
                     let timestamp = value.parse::<u64>().map_err(|_| {
                         ParseCommandError::InvalidArgValue(value, token, "set".to_string())
                     })?;
-                    match command.expire_at {
+                    match command.expiry {
                         Some(_) => return Err(ParseCommandError::InvalidFormat),
-                        None => command.expire_at = Some(timestamp),
+                        None => command.expiry = Some(Expiry::At(timestamp)),
                     }
                 } else if matches!(at_or_after_token.as_str(), "after") {
                     let value = parser
@@ -286,15 +904,9 @@ impl Set {
                     let millis = value.parse::<u64>().map_err(|_| {
                         ParseCommandError::InvalidArgValue(value, token, "set".to_string())
                     })?;
-
-                    let timestamp = SystemTime::now()
-                        .add(Duration::from_millis(millis))
-                        .duration_since(UNIX_EPOCH)?
-                        .as_secs();
-
-                    match command.expire_at {
+                    match command.expiry {
                         Some(_) => return Err(ParseCommandError::InvalidFormat),
-                        None => command.expire_at = Some(timestamp),
+                        None => command.expiry = Some(Expiry::After(millis)),
                     }
                 } else {
                     return Err(ParseCommandError::InvalidArg(
@@ -314,7 +926,7 @@ impl Set {
                         .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?
                         .to_lowercase();
                     if matches!(exists_token.as_str(), "exists") {
-                        if !command.if_not_exists && !command.if_exists {
+                        if !command.has_condition() {
                             command.if_not_exists = true
                         } else {
                             return Err(ParseCommandError::InvalidFormat);
@@ -326,11 +938,34 @@ impl Set {
                         ));
                     }
                 } else if matches!(not_or_exists_token.as_str(), "exists") {
-                    if !command.if_not_exists && !command.if_exists {
+                    if !command.has_condition() {
                         command.if_exists = true
                     } else {
                         return Err(ParseCommandError::InvalidFormat);
                     }
+                } else if matches!(not_or_exists_token.as_str(), "version") {
+                    let value = parser
+                        .next_as_string()?
+                        .ok_or_else(|| ParseCommandError::WrongArgCount("set".to_string()))?;
+                    let version = value.parse::<u64>().map_err(|_| {
+                        ParseCommandError::InvalidArgValue(
+                            value,
+                            not_or_exists_token,
+                            "set".to_string(),
+                        )
+                    })?;
+                    if !command.has_condition() {
+                        command.if_version = Some(version)
+                    } else {
+                        return Err(ParseCommandError::InvalidFormat);
+                    }
+                } else if matches!(not_or_exists_token.as_str(), "value") {
+                    let expected = parser.next_as_expected_value("set")?;
+                    if !command.has_condition() {
+                        command.if_value_equals = Some(expected)
+                    } else {
+                        return Err(ParseCommandError::InvalidFormat);
+                    }
                 } else {
                     return Err(ParseCommandError::InvalidArg(
                         not_or_exists_token,
@@ -345,8 +980,19 @@ impl Set {
         Ok(command)
     }
 
-    pub fn expire_at(&self) -> Option<u64> {
-        self.expire_at
+    /// Returns the unix timestamp in seconds at which the key expires, an expiry
+    /// relative to now is resolved against the current time.
+    pub fn expire_at(&self) -> Result<Option<u64>, SystemTimeError> {
+        match self.expiry {
+            Some(Expiry::At(timestamp)) => Ok(Some(timestamp)),
+            Some(Expiry::After(millis)) => Ok(Some(
+                SystemTime::now()
+                    .add(Duration::from_millis(millis))
+                    .duration_since(UNIX_EPOCH)?
+                    .as_secs(),
+            )),
+            None => Ok(None),
+        }
     }
 
     pub fn if_exists(&self) -> bool {
@@ -357,6 +1003,21 @@ impl Set {
         self.if_not_exists
     }
 
+    pub fn if_version(&self) -> Option<u64> {
+        self.if_version
+    }
+
+    pub fn if_value_equals(&self) -> Option<Bytes> {
+        self.if_value_equals.clone()
+    }
+
+    fn has_condition(&self) -> bool {
+        self.if_not_exists
+            || self.if_exists
+            || self.if_version.is_some()
+            || self.if_value_equals.is_some()
+    }
+
     pub fn key(&self) -> Bytes {
         self.key.clone()
     }
@@ -380,7 +1041,32 @@ impl Get {
             .next_as_bytes()?
             .ok_or_else(|| ParseCommandError::WrongArgCount("get".to_string()))?;
 
-        let command = Get { keyspace, key };
+        let mut command = Get {
+            keyspace,
+            key,
+            with_version: false,
+        };
+
+        if parser.has_remaining() {
+            let with_token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("get".to_string()))?
+                .to_lowercase();
+            if !matches!(with_token.as_str(), "with") {
+                return Err(ParseCommandError::InvalidArg(with_token, "get".to_string()));
+            }
+            let version_token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("get".to_string()))?
+                .to_lowercase();
+            if !matches!(version_token.as_str(), "version") {
+                return Err(ParseCommandError::InvalidArg(
+                    version_token,
+                    "get".to_string(),
+                ));
+            }
+            command.with_version = true;
+        }
 
         if parser.has_remaining() {
             return Err(ParseCommandError::WrongArgCount("get".to_string()));
@@ -396,6 +1082,10 @@ impl Get {
     pub fn key(&self) -> Bytes {
         self.key.clone()
     }
+
+    pub fn with_version(&self) -> bool {
+        self.with_version
+    }
 }
 
 impl Del {
@@ -408,10 +1098,37 @@ impl Del {
             .next_as_bytes()?
             .ok_or_else(|| ParseCommandError::WrongArgCount("del".to_string()))?;
 
-        let command = Del { keyspace, key };
+        let mut command = Del {
+            keyspace,
+            key,
+            if_value_equals: None,
+        };
 
-        if parser.has_remaining() {
-            return Err(ParseCommandError::WrongArgCount("del".to_string()));
+        while parser.has_remaining() {
+            let token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("del".to_string()))?
+                .to_lowercase();
+
+            if matches!(token.as_str(), "if") {
+                let value_token = parser
+                    .next_as_string()?
+                    .ok_or_else(|| ParseCommandError::WrongArgCount("del".to_string()))?
+                    .to_lowercase();
+                if !matches!(value_token.as_str(), "value") {
+                    return Err(ParseCommandError::InvalidArg(
+                        value_token,
+                        "del".to_string(),
+                    ));
+                }
+                let expected = parser.next_as_expected_value("del")?;
+                match command.if_value_equals {
+                    Some(_) => return Err(ParseCommandError::InvalidFormat),
+                    None => command.if_value_equals = Some(expected),
+                }
+            } else {
+                return Err(ParseCommandError::InvalidArg(token, "del".to_string()));
+            }
         }
 
         Ok(command)
@@ -424,6 +1141,10 @@ impl Del {
     pub fn key(&self) -> Bytes {
         self.key.clone()
     }
+
+    pub fn if_value_equals(&self) -> Option<Bytes> {
+        self.if_value_equals.clone()
+    }
 }
 
 impl Drop {
@@ -529,6 +1250,1574 @@ impl Ttl {
     }
 }
 
+impl JsonSet {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.set".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.set".to_string()))?;
+
+        let path = parser
+            .next_as_json_path("json.set")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.set".to_string()))?;
+
+        let data = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.set".to_string()))?;
+
+        let value = serde_json::from_slice(&data[..])
+            .map_err(|_| ParseCommandError::InvalidJson("json.set".to_string()))?;
+
+        let command = JsonSet {
+            keyspace,
+            key,
+            path,
+            value,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("json.set".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn value(&self) -> serde_json::Value {
+        self.value.clone()
+    }
+}
+
+impl JsonGet {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.get".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.get".to_string()))?;
+
+        let path = match parser.next_as_json_path("json.get")? {
+            Some(path) => path,
+            None => Path::parse("$").expect("root path is always valid"),
+        };
+
+        let mut command = JsonGet {
+            keyspace,
+            key,
+            path,
+            native: false,
+        };
+
+        while parser.has_remaining() {
+            let token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("json.get".to_string()))?
+                .to_lowercase();
+
+            if matches!(token.as_str(), "as") {
+                let format_token = parser
+                    .next_as_string()?
+                    .ok_or_else(|| ParseCommandError::WrongArgCount("json.get".to_string()))?
+                    .to_lowercase();
+                match format_token.as_str() {
+                    "native" => command.native = true,
+                    "string" => command.native = false,
+                    _ => {
+                        return Err(ParseCommandError::InvalidArgValue(
+                            format_token,
+                            token,
+                            "json.get".to_string(),
+                        ))
+                    }
+                }
+            } else {
+                return Err(ParseCommandError::InvalidArg(token, "json.get".to_string()));
+            }
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn native(&self) -> bool {
+        self.native
+    }
+}
+
+impl JsonDel {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.del".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.del".to_string()))?;
+
+        let path = match parser.next_as_json_path("json.del")? {
+            Some(path) => path,
+            None => Path::parse("$").expect("root path is always valid"),
+        };
+
+        let command = JsonDel {
+            keyspace,
+            key,
+            path,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("json.del".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl JsonIncrBy {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.numincrby".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.numincrby".to_string()))?;
+
+        let path = parser
+            .next_as_json_path("json.numincrby")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.numincrby".to_string()))?;
+
+        let value = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("json.numincrby".to_string()))?;
+
+        let by = value.parse::<Number>().map_err(|_| {
+            ParseCommandError::InvalidArgValue(
+                value,
+                "by".to_string(),
+                "json.numincrby".to_string(),
+            )
+        })?;
+
+        let command = JsonIncrBy {
+            keyspace,
+            key,
+            path,
+            by,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount(
+                "json.numincrby".to_string(),
+            ));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn by(&self) -> &Number {
+        &self.by
+    }
+}
+
+impl PfAdd {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("pfadd".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("pfadd".to_string()))?;
+
+        let elements = parser.remaining_as_bytes()?;
+
+        Ok(PfAdd {
+            keyspace,
+            key,
+            elements,
+        })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn elements(&self) -> &[Bytes] {
+        &self.elements
+    }
+}
+
+impl PfCount {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("pfcount".to_string()))?;
+
+        let keys = parser.remaining_as_bytes()?;
+
+        if keys.is_empty() {
+            return Err(ParseCommandError::WrongArgCount("pfcount".to_string()));
+        }
+
+        Ok(PfCount { keyspace, keys })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+}
+
+impl PfMerge {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("pfmerge".to_string()))?;
+
+        let destination = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("pfmerge".to_string()))?;
+
+        let sources = parser.remaining_as_bytes()?;
+
+        Ok(PfMerge {
+            keyspace,
+            destination,
+            sources,
+        })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn destination(&self) -> Bytes {
+        self.destination.clone()
+    }
+
+    pub fn sources(&self) -> &[Bytes] {
+        &self.sources
+    }
+}
+
+impl BfReserve {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bf.reserve".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bf.reserve".to_string()))?;
+
+        let value = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bf.reserve".to_string()))?;
+
+        let capacity = match value.parse::<u64>() {
+            Ok(capacity) if capacity > 0 => capacity,
+            _ => {
+                return Err(ParseCommandError::InvalidArgValue(
+                    value,
+                    "capacity".to_string(),
+                    "bf.reserve".to_string(),
+                ))
+            }
+        };
+
+        let value = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bf.reserve".to_string()))?;
+
+        let error_rate = match value.parse::<f64>() {
+            Ok(error_rate) if error_rate > 0.0 && error_rate < 1.0 => error_rate,
+            _ => {
+                return Err(ParseCommandError::InvalidArgValue(
+                    value,
+                    "error_rate".to_string(),
+                    "bf.reserve".to_string(),
+                ))
+            }
+        };
+
+        if capacity > bloom::MAX_BITS || bloom::num_bits(capacity, error_rate) > bloom::MAX_BITS {
+            return Err(ParseCommandError::InvalidArgValue(
+                capacity.to_string(),
+                "capacity".to_string(),
+                "bf.reserve".to_string(),
+            ));
+        }
+
+        let command = BfReserve {
+            keyspace,
+            key,
+            capacity,
+            error_rate,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("bf.reserve".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        self.error_rate
+    }
+}
+
+impl BfAdd {
+    fn parse(parser: &mut Parser, name: &str, multi: bool) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(name.to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(name.to_string()))?;
+
+        let items = parser.remaining_as_bytes()?;
+
+        if items.is_empty() || (!multi && items.len() > 1) {
+            return Err(ParseCommandError::WrongArgCount(name.to_string()));
+        }
+
+        Ok(BfAdd {
+            keyspace,
+            key,
+            items,
+        })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn items(&self) -> &[Bytes] {
+        &self.items
+    }
+}
+
+impl BfExists {
+    fn parse(parser: &mut Parser, name: &str, multi: bool) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(name.to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount(name.to_string()))?;
+
+        let items = parser.remaining_as_bytes()?;
+
+        if items.is_empty() || (!multi && items.len() > 1) {
+            return Err(ParseCommandError::WrongArgCount(name.to_string()));
+        }
+
+        Ok(BfExists {
+            keyspace,
+            key,
+            items,
+        })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn items(&self) -> &[Bytes] {
+        &self.items
+    }
+}
+
+// offsets are limited to 2^32 bits to stop a single command from allocating 512mb+ values
+const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+fn parse_bit_offset(parser: &mut Parser, command: &str) -> Result<u64, ParseCommandError> {
+    let offset = parser
+        .next_as_integer::<u64>("offset", command)?
+        .ok_or_else(|| ParseCommandError::WrongArgCount(command.to_string()))?;
+
+    if offset > MAX_BIT_OFFSET {
+        return Err(ParseCommandError::InvalidArgValue(
+            offset.to_string(),
+            "offset".to_string(),
+            command.to_string(),
+        ));
+    }
+
+    Ok(offset)
+}
+
+impl SetBit {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setbit".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setbit".to_string()))?;
+
+        let offset = parse_bit_offset(parser, "setbit")?;
+
+        let value = parser
+            .next_as_bit("setbit")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setbit".to_string()))?;
+
+        let command = SetBit {
+            keyspace,
+            key,
+            offset,
+            value,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("setbit".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn value(&self) -> bool {
+        self.value
+    }
+}
+
+impl GetBit {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getbit".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getbit".to_string()))?;
+
+        let offset = parse_bit_offset(parser, "getbit")?;
+
+        let command = GetBit {
+            keyspace,
+            key,
+            offset,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("getbit".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl BitCount {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitcount".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitcount".to_string()))?;
+
+        let mut command = BitCount {
+            keyspace,
+            key,
+            range: None,
+        };
+
+        if let Some(start) = parser.next_as_integer::<i64>("start", "bitcount")? {
+            let end = parser
+                .next_as_integer::<i64>("end", "bitcount")?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("bitcount".to_string()))?;
+            command.range = Some((start, end));
+        }
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("bitcount".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn range(&self) -> Option<(i64, i64)> {
+        self.range
+    }
+}
+
+impl BitPos {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitpos".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitpos".to_string()))?;
+
+        let bit = parser
+            .next_as_bit("bitpos")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitpos".to_string()))?;
+
+        let start = parser.next_as_integer::<i64>("start", "bitpos")?;
+        let end = parser.next_as_integer::<i64>("end", "bitpos")?;
+
+        let command = BitPos {
+            keyspace,
+            key,
+            bit,
+            start,
+            end,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("bitpos".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn bit(&self) -> bool {
+        self.bit
+    }
+
+    pub fn start(&self) -> Option<i64> {
+        self.start
+    }
+
+    pub fn end(&self) -> Option<i64> {
+        self.end
+    }
+}
+
+impl BitOp {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitop".to_string()))?;
+
+        let value = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitop".to_string()))?
+            .to_lowercase();
+
+        let operation = match value.as_str() {
+            "and" => BitOperation::And,
+            "or" => BitOperation::Or,
+            "xor" => BitOperation::Xor,
+            "not" => BitOperation::Not,
+            _ => {
+                return Err(ParseCommandError::InvalidArgValue(
+                    value,
+                    "operation".to_string(),
+                    "bitop".to_string(),
+                ))
+            }
+        };
+
+        let destination = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bitop".to_string()))?;
+
+        let keys = parser.remaining_as_bytes()?;
+
+        if keys.is_empty() || (operation == BitOperation::Not && keys.len() > 1) {
+            return Err(ParseCommandError::WrongArgCount("bitop".to_string()));
+        }
+
+        Ok(BitOp {
+            keyspace,
+            operation,
+            destination,
+            keys,
+        })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn operation(&self) -> BitOperation {
+        self.operation
+    }
+
+    pub fn destination(&self) -> Bytes {
+        self.destination.clone()
+    }
+
+    pub fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+}
+
+// same as the limit on bit offsets, stops a single command from allocating huge values
+const MAX_RANGE_OFFSET: u64 = (1 << 29) - 1;
+
+impl Append {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("append".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("append".to_string()))?;
+
+        let value = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("append".to_string()))?;
+
+        let command = Append {
+            keyspace,
+            key,
+            value,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("append".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn value(&self) -> Bytes {
+        self.value.clone()
+    }
+}
+
+impl GetRange {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getrange".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getrange".to_string()))?;
+
+        let start = parser
+            .next_as_integer::<i64>("start", "getrange")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getrange".to_string()))?;
+
+        let end = parser
+            .next_as_integer::<i64>("end", "getrange")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("getrange".to_string()))?;
+
+        let command = GetRange {
+            keyspace,
+            key,
+            start,
+            end,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("getrange".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+}
+
+impl SetRange {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setrange".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setrange".to_string()))?;
+
+        let offset = parser
+            .next_as_integer::<u64>("offset", "setrange")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setrange".to_string()))?;
+
+        if offset > MAX_RANGE_OFFSET {
+            return Err(ParseCommandError::InvalidArgValue(
+                offset.to_string(),
+                "offset".to_string(),
+                "setrange".to_string(),
+            ));
+        }
+
+        let value = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("setrange".to_string()))?;
+
+        let command = SetRange {
+            keyspace,
+            key,
+            offset,
+            value,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("setrange".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn value(&self) -> Bytes {
+        self.value.clone()
+    }
+}
+
+impl StrLen {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("strlen".to_string()))?;
+
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("strlen".to_string()))?;
+
+        let command = StrLen { keyspace, key };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("strlen".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+}
+
+impl Subscribe {
+    fn parse(parser: &mut Parser, name: &str) -> Result<Self, ParseCommandError> {
+        let channels = parser.remaining_as_bytes()?;
+
+        if channels.is_empty() {
+            return Err(ParseCommandError::WrongArgCount(name.to_string()));
+        }
+
+        Ok(Subscribe { channels })
+    }
+
+    pub fn channels(&self) -> &[Bytes] {
+        &self.channels
+    }
+}
+
+impl Unsubscribe {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let channels = parser.remaining_as_bytes()?;
+        Ok(Unsubscribe { channels })
+    }
+
+    /// Channels to unsubscribe from, empty if the connection should be
+    /// unsubscribed from all of them.
+    pub fn channels(&self) -> &[Bytes] {
+        &self.channels
+    }
+}
+
+impl Publish {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let channel = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("publish".to_string()))?;
+
+        let message = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("publish".to_string()))?;
+
+        let command = Publish { channel, message };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("publish".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn channel(&self) -> Bytes {
+        self.channel.clone()
+    }
+
+    pub fn message(&self) -> Bytes {
+        self.message.clone()
+    }
+}
+
+// parses the `EVENTS` token that follows `WATCH` and `UNWATCH`
+fn parse_events_token(parser: &mut Parser, name: &str) -> Result<(), ParseCommandError> {
+    let events_token = parser
+        .next_as_string()?
+        .ok_or_else(|| ParseCommandError::WrongArgCount(name.to_string()))?
+        .to_lowercase();
+    if !matches!(events_token.as_str(), "events") {
+        return Err(ParseCommandError::InvalidArg(
+            events_token,
+            name.to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl WatchEvents {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        parse_events_token(parser, "watch")?;
+
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("watch".to_string()))?;
+
+        let mut command = WatchEvents {
+            keyspace,
+            pattern: None,
+        };
+
+        if parser.has_remaining() {
+            let match_token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("watch".to_string()))?
+                .to_lowercase();
+            if !matches!(match_token.as_str(), "match") {
+                return Err(ParseCommandError::InvalidArg(
+                    match_token,
+                    "watch".to_string(),
+                ));
+            }
+            let pattern = parser
+                .next_as_bytes()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("watch".to_string()))?;
+            command.pattern = Some(pattern);
+        }
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("watch".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn pattern(&self) -> Option<Bytes> {
+        self.pattern.clone()
+    }
+}
+
+impl UnwatchEvents {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        parse_events_token(parser, "unwatch")?;
+
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("unwatch".to_string()))?;
+
+        let command = UnwatchEvents { keyspace };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("unwatch".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+}
+
+impl MGet {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("mget".to_string()))?;
+
+        let keys = parser.remaining_as_bytes()?;
+
+        if keys.is_empty() {
+            return Err(ParseCommandError::WrongArgCount("mget".to_string()));
+        }
+
+        Ok(MGet { keyspace, keys })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+}
+
+impl Tracking {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let on_or_off_token = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("tracking".to_string()))?
+            .to_lowercase();
+
+        if matches!(on_or_off_token.as_str(), "off") {
+            if parser.has_remaining() {
+                return Err(ParseCommandError::WrongArgCount("tracking".to_string()));
+            }
+            return Ok(Tracking { mode: None });
+        } else if !matches!(on_or_off_token.as_str(), "on") {
+            return Err(ParseCommandError::InvalidArg(
+                on_or_off_token,
+                "tracking".to_string(),
+            ));
+        }
+
+        if !parser.has_remaining() {
+            return Ok(Tracking {
+                mode: Some(TrackingMode::Default),
+            });
+        }
+
+        let bcast_token = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("tracking".to_string()))?
+            .to_lowercase();
+        if !matches!(bcast_token.as_str(), "bcast") {
+            return Err(ParseCommandError::InvalidArg(
+                bcast_token,
+                "tracking".to_string(),
+            ));
+        }
+
+        let mut prefixes = Vec::new();
+        while parser.has_remaining() {
+            let prefix_token = parser
+                .next_as_string()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("tracking".to_string()))?
+                .to_lowercase();
+            if !matches!(prefix_token.as_str(), "prefix") {
+                return Err(ParseCommandError::InvalidArg(
+                    prefix_token,
+                    "tracking".to_string(),
+                ));
+            }
+            let prefix = parser
+                .next_as_bytes()?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("tracking".to_string()))?;
+            prefixes.push(prefix);
+        }
+
+        Ok(Tracking {
+            mode: Some(TrackingMode::Broadcast(prefixes)),
+        })
+    }
+
+    /// Returns the requested tracking mode, None if tracking should be turned off.
+    pub fn mode(&self) -> Option<&TrackingMode> {
+        self.mode.as_ref()
+    }
+}
+
+impl Auth {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let username = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("auth".to_string()))?;
+
+        let password = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("auth".to_string()))?;
+
+        let command = Auth { username, password };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("auth".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn password(&self) -> &[u8] {
+        &self.password
+    }
+}
+
+impl ClientKill {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let target = parser
+            .next_as_string()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("client".to_string()))?;
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("client".to_string()));
+        }
+
+        // ids are numbers while addresses always contain a port or are `unix`
+        match target.parse::<u64>() {
+            Ok(id) => Ok(ClientKill::Id(id)),
+            Err(_) => Ok(ClientKill::Addr(target)),
+        }
+    }
+}
+
+impl ClientSetName {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let name = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("client".to_string()))?;
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("client".to_string()));
+        }
+
+        Ok(ClientSetName { name })
+    }
+
+    pub fn name(&self) -> Bytes {
+        self.name.clone()
+    }
+}
+
+impl ClientPause {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let millis = parser
+            .next_as_integer::<u64>("timeout", "client")?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("client".to_string()))?;
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("client".to_string()));
+        }
+
+        Ok(ClientPause {
+            duration: Duration::from_millis(millis),
+        })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+// parses the subcommands of `CLIENT`
+fn parse_client(parser: &mut Parser) -> Result<Command, ParseCommandError> {
+    let subcommand = parser
+        .next_as_string()?
+        .ok_or_else(|| ParseCommandError::WrongArgCount("client".to_string()))?
+        .to_lowercase();
+
+    match subcommand.as_str() {
+        "list" if !parser.has_remaining() => Ok(Command::ClientList),
+        "info" if !parser.has_remaining() => Ok(Command::ClientInfo),
+        "list" | "info" => Err(ParseCommandError::WrongArgCount("client".to_string())),
+        "kill" => Ok(Command::ClientKill(ClientKill::parse(parser)?)),
+        "setname" => Ok(Command::ClientSetName(ClientSetName::parse(parser)?)),
+        "pause" => Ok(Command::ClientPause(ClientPause::parse(parser)?)),
+        _ => Err(ParseCommandError::InvalidArg(
+            subcommand,
+            "client".to_string(),
+        )),
+    }
+}
+
+impl Info {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let section = match parser.next_as_string()? {
+            Some(section) => Some(InfoSection::parse(&section.to_lowercase())?),
+            None => None,
+        };
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("info".to_string()));
+        }
+
+        Ok(Info { section })
+    }
+
+    /// Returns the requested section, None if every section was requested.
+    pub fn section(&self) -> Option<InfoSection> {
+        self.section
+    }
+}
+
+impl MemoryUsage {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("memory".to_string()))?;
+        let key = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("memory".to_string()))?;
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("memory".to_string()));
+        }
+
+        Ok(MemoryUsage { keyspace, key })
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    pub fn key(&self) -> Bytes {
+        self.key.clone()
+    }
+}
+
+// parses the subcommands of `MEMORY`
+fn parse_memory(parser: &mut Parser) -> Result<Command, ParseCommandError> {
+    let subcommand = parser
+        .next_as_string()?
+        .ok_or_else(|| ParseCommandError::WrongArgCount("memory".to_string()))?
+        .to_lowercase();
+
+    match subcommand.as_str() {
+        "usage" => Ok(Command::MemoryUsage(MemoryUsage::parse(parser)?)),
+        "stats" if !parser.has_remaining() => Ok(Command::MemoryStats),
+        "stats" => Err(ParseCommandError::WrongArgCount("memory".to_string())),
+        _ => Err(ParseCommandError::InvalidArg(
+            subcommand,
+            "memory".to_string(),
+        )),
+    }
+}
+
+impl BigKeys {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("bigkeys".to_string()))?;
+
+        let mut command = BigKeys {
+            keyspace,
+            limit: 10,
+        };
+
+        if let Some(token) = parser.next_as_string()? {
+            let token = token.to_lowercase();
+            if token != "limit" {
+                return Err(ParseCommandError::InvalidArg(token, "bigkeys".to_string()));
+            }
+            command.limit = parser
+                .next_as_integer::<usize>("limit", "bigkeys")?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("bigkeys".to_string()))?;
+        }
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("bigkeys".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    /// Returns the max number of keys to report, largest first.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl HotKeys {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let keyspace = parser
+            .next_as_bytes()?
+            .ok_or_else(|| ParseCommandError::WrongArgCount("hotkeys".to_string()))?;
+
+        let mut command = HotKeys {
+            keyspace,
+            limit: 10,
+        };
+
+        if let Some(token) = parser.next_as_string()? {
+            let token = token.to_lowercase();
+            if token != "limit" {
+                return Err(ParseCommandError::InvalidArg(token, "hotkeys".to_string()));
+            }
+            command.limit = parser
+                .next_as_integer::<usize>("limit", "hotkeys")?
+                .ok_or_else(|| ParseCommandError::WrongArgCount("hotkeys".to_string()))?;
+        }
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("hotkeys".to_string()));
+        }
+
+        Ok(command)
+    }
+
+    pub fn keyspace(&self) -> Bytes {
+        self.keyspace.clone()
+    }
+
+    /// Returns the max number of keys to report, hottest first.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl SlowLogGet {
+    fn parse(parser: &mut Parser) -> Result<Self, ParseCommandError> {
+        let count = parser
+            .next_as_integer::<usize>("count", "slowlog")?
+            .unwrap_or(10);
+
+        if parser.has_remaining() {
+            return Err(ParseCommandError::WrongArgCount("slowlog".to_string()));
+        }
+
+        Ok(SlowLogGet { count })
+    }
+
+    /// Returns the max number of entries to return, newest first.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+// parses the subcommands of `SLOWLOG`
+fn parse_slowlog(parser: &mut Parser) -> Result<Command, ParseCommandError> {
+    let subcommand = parser
+        .next_as_string()?
+        .ok_or_else(|| ParseCommandError::WrongArgCount("slowlog".to_string()))?
+        .to_lowercase();
+
+    match subcommand.as_str() {
+        "get" => Ok(Command::SlowLogGet(SlowLogGet::parse(parser)?)),
+        "len" if !parser.has_remaining() => Ok(Command::SlowLogLen),
+        "reset" if !parser.has_remaining() => Ok(Command::SlowLogReset),
+        "len" | "reset" => Err(ParseCommandError::WrongArgCount("slowlog".to_string())),
+        _ => Err(ParseCommandError::InvalidArg(
+            subcommand,
+            "slowlog".to_string(),
+        )),
+    }
+}
+
+impl InfoSection {
+    pub const ALL: [InfoSection; 5] = [
+        InfoSection::Server,
+        InfoSection::Clients,
+        InfoSection::Stats,
+        InfoSection::Memory,
+        InfoSection::Keyspaces,
+    ];
+
+    fn parse(section: &str) -> Result<Self, ParseCommandError> {
+        InfoSection::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == section)
+            .ok_or_else(|| ParseCommandError::InvalidArg(section.to_string(), "info".to_string()))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InfoSection::Server => "server",
+            InfoSection::Clients => "clients",
+            InfoSection::Stats => "stats",
+            InfoSection::Memory => "memory",
+            InfoSection::Keyspaces => "keyspaces",
+        }
+    }
+}
+
+impl Command {
+    /// Returns the keyspace the command operates on, commands that manage keyspaces
+    /// or the connection are not scoped to a keyspace.
+    pub fn keyspace(&self) -> Option<Bytes> {
+        match self {
+            Command::Set(cmd) => Some(cmd.keyspace()),
+            Command::Get(cmd) => Some(cmd.keyspace()),
+            Command::Del(cmd) => Some(cmd.keyspace()),
+            Command::Count(cmd) => Some(cmd.keyspace()),
+            Command::Ttl(cmd) => Some(cmd.keyspace()),
+            Command::JsonSet(cmd) => Some(cmd.keyspace()),
+            Command::JsonGet(cmd) => Some(cmd.keyspace()),
+            Command::JsonDel(cmd) => Some(cmd.keyspace()),
+            Command::JsonIncrBy(cmd) => Some(cmd.keyspace()),
+            Command::PfAdd(cmd) => Some(cmd.keyspace()),
+            Command::PfCount(cmd) => Some(cmd.keyspace()),
+            Command::PfMerge(cmd) => Some(cmd.keyspace()),
+            Command::BfReserve(cmd) => Some(cmd.keyspace()),
+            Command::BfAdd(cmd) => Some(cmd.keyspace()),
+            Command::BfMAdd(cmd) => Some(cmd.keyspace()),
+            Command::BfExists(cmd) => Some(cmd.keyspace()),
+            Command::BfMExists(cmd) => Some(cmd.keyspace()),
+            Command::SetBit(cmd) => Some(cmd.keyspace()),
+            Command::GetBit(cmd) => Some(cmd.keyspace()),
+            Command::BitCount(cmd) => Some(cmd.keyspace()),
+            Command::BitPos(cmd) => Some(cmd.keyspace()),
+            Command::BitOp(cmd) => Some(cmd.keyspace()),
+            Command::Append(cmd) => Some(cmd.keyspace()),
+            Command::GetRange(cmd) => Some(cmd.keyspace()),
+            Command::SetRange(cmd) => Some(cmd.keyspace()),
+            Command::StrLen(cmd) => Some(cmd.keyspace()),
+            Command::MGet(cmd) => Some(cmd.keyspace()),
+            Command::MemoryUsage(cmd) => Some(cmd.keyspace()),
+            Command::BigKeys(cmd) => Some(cmd.keyspace()),
+            Command::HotKeys(cmd) => Some(cmd.keyspace()),
+            Command::Truncate(cmd) => Some(cmd.keyspace()),
+            Command::Create(_)
+            | Command::Alter(_)
+            | Command::RenameKeyspace(_)
+            | Command::CopyKeyspace(_)
+            | Command::Drop(_)
+            | Command::Ping
+            | Command::Keyspaces
+            | Command::Multi
+            | Command::Exec
+            | Command::Discard
+            | Command::Subscribe(_)
+            | Command::PSubscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::Publish(_)
+            | Command::WatchEvents(_)
+            | Command::UnwatchEvents(_)
+            | Command::Tracking(_)
+            | Command::Auth(_)
+            | Command::ClientList
+            | Command::ClientInfo
+            | Command::ClientKill(_)
+            | Command::ClientSetName(_)
+            | Command::ClientPause(_)
+            | Command::Info(_)
+            | Command::SlowLogGet(_)
+            | Command::SlowLogLen
+            | Command::SlowLogReset
+            | Command::Monitor
+            | Command::MemoryStats => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Create(_) => "create",
+            Command::Alter(_) => "alter",
+            Command::RenameKeyspace(_) => "rename",
+            Command::CopyKeyspace(_) => "copy",
+            Command::Truncate(_) => "truncate",
+            Command::Set(_) => "set",
+            Command::Get(_) => "get",
+            Command::Del(_) => "del",
+            Command::Drop(_) => "drop",
+            Command::Count(_) => "count",
+            Command::Ttl(_) => "ttl",
+            Command::JsonSet(_) => "json.set",
+            Command::JsonGet(_) => "json.get",
+            Command::JsonDel(_) => "json.del",
+            Command::JsonIncrBy(_) => "json.numincrby",
+            Command::PfAdd(_) => "pfadd",
+            Command::PfCount(_) => "pfcount",
+            Command::PfMerge(_) => "pfmerge",
+            Command::BfReserve(_) => "bf.reserve",
+            Command::BfAdd(_) => "bf.add",
+            Command::BfMAdd(_) => "bf.madd",
+            Command::BfExists(_) => "bf.exists",
+            Command::BfMExists(_) => "bf.mexists",
+            Command::SetBit(_) => "setbit",
+            Command::GetBit(_) => "getbit",
+            Command::BitCount(_) => "bitcount",
+            Command::BitPos(_) => "bitpos",
+            Command::BitOp(_) => "bitop",
+            Command::Append(_) => "append",
+            Command::GetRange(_) => "getrange",
+            Command::SetRange(_) => "setrange",
+            Command::StrLen(_) => "strlen",
+            Command::Ping => "ping",
+            Command::Keyspaces => "keyspaces",
+            Command::Multi => "multi",
+            Command::Exec => "exec",
+            Command::Discard => "discard",
+            Command::Subscribe(_) => "subscribe",
+            Command::PSubscribe(_) => "psubscribe",
+            Command::Unsubscribe(_) => "unsubscribe",
+            Command::PUnsubscribe(_) => "punsubscribe",
+            Command::Publish(_) => "publish",
+            Command::WatchEvents(_) => "watch",
+            Command::UnwatchEvents(_) => "unwatch",
+            Command::MGet(_) => "mget",
+            Command::Tracking(_) => "tracking",
+            Command::Auth(_) => "auth",
+            Command::ClientList
+            | Command::ClientInfo
+            | Command::ClientKill(_)
+            | Command::ClientSetName(_)
+            | Command::ClientPause(_) => "client",
+            Command::Info(_) => "info",
+            Command::SlowLogGet(_) | Command::SlowLogLen | Command::SlowLogReset => "slowlog",
+            Command::Monitor => "monitor",
+            Command::MemoryUsage(_) | Command::MemoryStats => "memory",
+            Command::BigKeys(_) => "bigkeys",
+            Command::HotKeys(_) => "hotkeys",
+        }
+    }
+}
+
 pub fn parse(frame: Frame) -> Result<Command, ParseCommandError> {
     let mut parser = Parser::new(frame)?;
     let command = match parser.next().ok_or(ParseCommandError::InvalidFormat)? {
@@ -538,14 +2827,74 @@ pub fn parse(frame: Frame) -> Result<Command, ParseCommandError> {
 
     match command.as_str() {
         "create" => Ok(Command::Create(Create::parse(&mut parser)?)),
+        "alter" => Ok(Command::Alter(Alter::parse(&mut parser)?)),
+        "rename" => Ok(Command::RenameKeyspace(RenameKeyspace::parse(&mut parser)?)),
+        "copy" => Ok(Command::CopyKeyspace(CopyKeyspace::parse(&mut parser)?)),
+        "truncate" => Ok(Command::Truncate(Truncate::parse(&mut parser)?)),
         "set" => Ok(Command::Set(Set::parse(&mut parser)?)),
         "get" => Ok(Command::Get(Get::parse(&mut parser)?)),
         "del" => Ok(Command::Del(Del::parse(&mut parser)?)),
         "drop" => Ok(Command::Drop(Drop::parse(&mut parser)?)),
         "count" => Ok(Command::Count(Count::parse(&mut parser)?)),
         "ttl" => Ok(Command::Ttl(Ttl::parse(&mut parser)?)),
+        "json.set" => Ok(Command::JsonSet(JsonSet::parse(&mut parser)?)),
+        "json.get" => Ok(Command::JsonGet(JsonGet::parse(&mut parser)?)),
+        "json.del" => Ok(Command::JsonDel(JsonDel::parse(&mut parser)?)),
+        "json.numincrby" => Ok(Command::JsonIncrBy(JsonIncrBy::parse(&mut parser)?)),
+        "pfadd" => Ok(Command::PfAdd(PfAdd::parse(&mut parser)?)),
+        "pfcount" => Ok(Command::PfCount(PfCount::parse(&mut parser)?)),
+        "pfmerge" => Ok(Command::PfMerge(PfMerge::parse(&mut parser)?)),
+        "bf.reserve" => Ok(Command::BfReserve(BfReserve::parse(&mut parser)?)),
+        "bf.add" => Ok(Command::BfAdd(BfAdd::parse(&mut parser, "bf.add", false)?)),
+        "bf.madd" => Ok(Command::BfMAdd(BfAdd::parse(&mut parser, "bf.madd", true)?)),
+        "bf.exists" => Ok(Command::BfExists(BfExists::parse(
+            &mut parser,
+            "bf.exists",
+            false,
+        )?)),
+        "bf.mexists" => Ok(Command::BfMExists(BfExists::parse(
+            &mut parser,
+            "bf.mexists",
+            true,
+        )?)),
+        "setbit" => Ok(Command::SetBit(SetBit::parse(&mut parser)?)),
+        "getbit" => Ok(Command::GetBit(GetBit::parse(&mut parser)?)),
+        "bitcount" => Ok(Command::BitCount(BitCount::parse(&mut parser)?)),
+        "bitpos" => Ok(Command::BitPos(BitPos::parse(&mut parser)?)),
+        "bitop" => Ok(Command::BitOp(BitOp::parse(&mut parser)?)),
+        "append" => Ok(Command::Append(Append::parse(&mut parser)?)),
+        "getrange" => Ok(Command::GetRange(GetRange::parse(&mut parser)?)),
+        "setrange" => Ok(Command::SetRange(SetRange::parse(&mut parser)?)),
+        "strlen" => Ok(Command::StrLen(StrLen::parse(&mut parser)?)),
         "ping" => Ok(Command::Ping),
         "keyspaces" => Ok(Command::Keyspaces),
+        "multi" => Ok(Command::Multi),
+        "exec" => Ok(Command::Exec),
+        "discard" => Ok(Command::Discard),
+        "subscribe" => Ok(Command::Subscribe(Subscribe::parse(
+            &mut parser,
+            "subscribe",
+        )?)),
+        "psubscribe" => Ok(Command::PSubscribe(Subscribe::parse(
+            &mut parser,
+            "psubscribe",
+        )?)),
+        "unsubscribe" => Ok(Command::Unsubscribe(Unsubscribe::parse(&mut parser)?)),
+        "punsubscribe" => Ok(Command::PUnsubscribe(Unsubscribe::parse(&mut parser)?)),
+        "publish" => Ok(Command::Publish(Publish::parse(&mut parser)?)),
+        "watch" => Ok(Command::WatchEvents(WatchEvents::parse(&mut parser)?)),
+        "unwatch" => Ok(Command::UnwatchEvents(UnwatchEvents::parse(&mut parser)?)),
+        "mget" => Ok(Command::MGet(MGet::parse(&mut parser)?)),
+        "tracking" => Ok(Command::Tracking(Tracking::parse(&mut parser)?)),
+        "auth" => Ok(Command::Auth(Auth::parse(&mut parser)?)),
+        "client" => parse_client(&mut parser),
+        "info" => Ok(Command::Info(Info::parse(&mut parser)?)),
+        "slowlog" => parse_slowlog(&mut parser),
+        "memory" => parse_memory(&mut parser),
+        "bigkeys" => Ok(Command::BigKeys(BigKeys::parse(&mut parser)?)),
+        "hotkeys" => Ok(Command::HotKeys(HotKeys::parse(&mut parser)?)),
+        "monitor" if !parser.has_remaining() => Ok(Command::Monitor),
+        "monitor" => Err(ParseCommandError::WrongArgCount("monitor".to_string())),
         _ => Err(ParseCommandError::UnknownCommand(command)),
     }
 }
@@ -1,12 +1,22 @@
 use super::parse;
+use crate::bitmap::BitOperation;
 use crate::db::Evictor;
+use crate::json::Path;
 use crate::{
-    command::{Command, Count, Create, Del, Drop, Get, Set, Ttl},
+    command::{
+        Alter, Append, Auth, BfAdd, BfExists, BfReserve, BigKeys, BitCount, BitOp, BitPos,
+        ClientKill, ClientPause, ClientSetName, Command, CopyKeyspace, Count, Create, Del, Drop,
+        Expiry, Get, GetBit, GetRange, HotKeys, Info, InfoSection, JsonDel, JsonGet, JsonIncrBy,
+        JsonSet, KeyspaceOption, MGet, MemoryUsage, PfAdd, PfCount, PfMerge, Publish,
+        RenameKeyspace, Set, SetBit, SetRange, SlowLogGet, StrLen, Subscribe, Tracking, Truncate,
+        Ttl, Unsubscribe, UnwatchEvents, WatchEvents,
+    },
     frame::Frame,
+    tracking::TrackingMode,
 };
 use bytes::Bytes;
-use std::ops::Add;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::{json, Number};
+use std::time::Duration;
 
 fn get_frame_from_str(str: &'static str) -> Frame {
     Frame::String(Bytes::from(str))
@@ -42,6 +52,7 @@ fn parse_given_create_command_with_keyspace_and_no_config_returns_create() {
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Nop,
+            options: Vec::new(),
             if_not_exists: false,
             keyspace: Bytes::from("foo")
         })
@@ -81,6 +92,7 @@ fn parse_given_create_command_with_lru_evictor_returns_create() {
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Lru,
+            options: Vec::new(),
             if_not_exists: false,
             keyspace: Bytes::from("foo")
         })
@@ -99,6 +111,7 @@ fn parse_given_create_command_with_random_evictor_returns_create() {
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Random,
+            options: Vec::new(),
             if_not_exists: false,
             keyspace: Bytes::from("foo")
         })
@@ -117,6 +130,7 @@ fn parse_given_create_command_with_nop_evictor_returns_create() {
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Nop,
+            options: Vec::new(),
             if_not_exists: false,
             keyspace: Bytes::from("foo")
         })
@@ -176,6 +190,7 @@ fn parse_given_create_command_with_if_not_exists_flag_returns_create() {
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Lru,
+            options: Vec::new(),
             if_not_exists: true,
             keyspace: Bytes::from("foo")
         })
@@ -198,6 +213,7 @@ fn parse_given_create_command_with_if_not_exists_flag_and_evictor_position_rever
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Lru,
+            options: Vec::new(),
             if_not_exists: true,
             keyspace: Bytes::from("foo")
         })
@@ -217,6 +233,7 @@ fn parse_given_create_command_with_if_not_exists_flag_and_default_evictor_return
         parse(Frame::Array(command)).unwrap(),
         Command::Create(Create {
             evictor: Evictor::Nop,
+            options: Vec::new(),
             if_not_exists: true,
             keyspace: Bytes::from("foo")
         })
@@ -274,8 +291,10 @@ fn parse_given_set_command_without_config_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: None,
-            if_exists: false
+            expiry: None,
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -337,8 +356,10 @@ fn parse_given_set_command_with_expire_at_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: Some(1667041052),
-            if_exists: false
+            expiry: Some(Expiry::At(1667041052)),
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -382,7 +403,6 @@ fn parse_given_set_command_with_invalid_expire_after_value_returns_error() {
     assert!(parse(Frame::Array(command)).is_err())
 }
 
-// FIXME: this is a finicky test, find a better way to test the timestamp
 #[test]
 fn parse_given_set_command_with_expire_after_returns_set() {
     let command = vec![
@@ -395,12 +415,6 @@ fn parse_given_set_command_with_expire_after_returns_set() {
         get_frame_from_str("60000"),
     ];
 
-    let timestamp = SystemTime::now()
-        .add(Duration::from_millis(60000))
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
     assert_eq!(
         parse(Frame::Array(command)).unwrap(),
         Command::Set(Set {
@@ -408,8 +422,10 @@ fn parse_given_set_command_with_expire_after_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: Some(timestamp),
-            if_exists: false
+            expiry: Some(Expiry::After(60000)),
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -480,8 +496,10 @@ fn parse_given_set_command_with_if_exists_flag_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: None,
-            if_exists: true
+            expiry: None,
+            if_exists: true,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -538,8 +556,10 @@ fn parse_given_set_command_with_if_not_exists_flag_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: true,
-            expire_at: None,
-            if_exists: false
+            expiry: None,
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -585,8 +605,10 @@ fn parse_given_set_command_with_if_not_exists_and_tll_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: true,
-            expire_at: Some(1667041052),
-            if_exists: false
+            expiry: Some(Expiry::At(1667041052)),
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -612,8 +634,10 @@ fn parse_given_set_command_with_if_exists_and_tll_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: Some(1667041052),
-            if_exists: true
+            expiry: Some(Expiry::At(1667041052)),
+            if_exists: true,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -640,8 +664,10 @@ fn parse_given_set_command_with_if_not_exists_and_tll_reverse_order_returns_set(
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: true,
-            expire_at: Some(1667041052),
-            if_exists: false
+            expiry: Some(Expiry::At(1667041052)),
+            if_exists: false,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -667,8 +693,10 @@ fn parse_given_set_command_with_if_exists_and_tll_reverse_order_returns_set() {
             key: Bytes::from("foo"),
             value: Bytes::from("bar"),
             if_not_exists: false,
-            expire_at: Some(1667041052),
-            if_exists: true
+            expiry: Some(Expiry::At(1667041052)),
+            if_exists: true,
+            if_version: None,
+            if_value_equals: None
         })
     );
 }
@@ -698,6 +726,7 @@ fn parse_given_get_returns_get() {
         Command::Get(Get {
             keyspace: Bytes::from("foo"),
             key: Bytes::from("bar"),
+            with_version: false,
         })
     );
 }
@@ -727,8 +756,176 @@ fn parse_given_del_returns_del() {
         Command::Del(Del {
             keyspace: Bytes::from("foo"),
             key: Bytes::from("bar"),
+            if_value_equals: None,
+        })
+    );
+}
+
+#[test]
+fn parse_given_create_command_with_options_returns_create() {
+    let command = vec![
+        get_frame_from_str("create"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("max"),
+        get_frame_from_str("keys"),
+        get_frame_from_str("1000"),
+        get_frame_from_str("evictor"),
+        get_frame_from_str("lru"),
+        get_frame_from_str("MAX"),
+        get_frame_from_str("MEMORY"),
+        get_frame_from_str("100MB"),
+        get_frame_from_str("default"),
+        get_frame_from_str("expire"),
+        get_frame_from_str("after"),
+        get_frame_from_str("60000"),
+        get_frame_from_str("max"),
+        get_frame_from_str("value"),
+        get_frame_from_str("size"),
+        get_frame_from_str("512"),
+        get_frame_from_str("readonly"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Create(Create {
+            evictor: Evictor::Lru,
+            options: vec![
+                KeyspaceOption::MaxKeys(1000),
+                KeyspaceOption::MaxMemory(100 * 1024 * 1024),
+                KeyspaceOption::DefaultExpireAfter(60000),
+                KeyspaceOption::MaxValueSize(512),
+                KeyspaceOption::ReadOnly(true),
+            ],
+            if_not_exists: false,
+            keyspace: Bytes::from("foo")
+        })
+    );
+}
+
+#[test]
+fn parse_given_create_command_with_invalid_max_memory_returns_error() {
+    let command = vec![
+        get_frame_from_str("create"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("max"),
+        get_frame_from_str("memory"),
+        get_frame_from_str("100tb"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err());
+}
+
+#[test]
+fn parse_given_alter_command_returns_alter() {
+    let command = vec![
+        get_frame_from_str("alter"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("readwrite"),
+        get_frame_from_str("max"),
+        get_frame_from_str("memory"),
+        get_frame_from_str("2kb"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Alter(Alter {
+            keyspace: Bytes::from("foo"),
+            evictor: None,
+            options: vec![
+                KeyspaceOption::ReadOnly(false),
+                KeyspaceOption::MaxMemory(2048),
+            ],
+        })
+    );
+}
+
+#[test]
+fn parse_given_alter_command_with_evictor_returns_alter() {
+    let command = vec![
+        get_frame_from_str("alter"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("evictor"),
+        get_frame_from_str("LRU"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Alter(Alter {
+            keyspace: Bytes::from("foo"),
+            evictor: Some(Evictor::Lru),
+            options: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn parse_given_alter_command_without_options_returns_error() {
+    let command = vec![get_frame_from_str("alter"), get_frame_from_str("foo")];
+    assert!(parse(Frame::Array(command)).is_err());
+
+    let command = vec![
+        get_frame_from_str("alter"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("evictor"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err());
+}
+
+#[test]
+fn parse_given_rename_keyspace_command_returns_rename_keyspace() {
+    let command = vec![
+        get_frame_from_str("rename"),
+        get_frame_from_str("KEYSPACE"),
+        get_frame_from_str("next"),
+        get_frame_from_str("TO"),
+        get_frame_from_str("current"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::RenameKeyspace(RenameKeyspace {
+            source: Bytes::from("next"),
+            destination: Bytes::from("current"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_copy_keyspace_command_returns_copy_keyspace() {
+    let command = vec![
+        get_frame_from_str("copy"),
+        get_frame_from_str("keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("to"),
+        get_frame_from_str("bar"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::CopyKeyspace(CopyKeyspace {
+            source: Bytes::from("foo"),
+            destination: Bytes::from("bar"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_copy_keyspace_command_without_to_returns_error() {
+    let command = vec![
+        get_frame_from_str("copy"),
+        get_frame_from_str("keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err());
+}
+
+#[test]
+fn parse_given_truncate_command_returns_truncate() {
+    let command = vec![get_frame_from_str("truncate"), get_frame_from_str("foo")];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Truncate(Truncate {
+            keyspace: Bytes::from("foo"),
         })
     );
+
+    let command = vec![get_frame_from_str("truncate")];
+    assert!(parse(Frame::Array(command)).is_err());
 }
 
 #[test]
@@ -848,3 +1045,1246 @@ fn parse_given_ttl_returns_ttl() {
         })
     );
 }
+
+#[test]
+fn parse_given_json_set_without_value_returns_error() {
+    let command = vec![
+        get_frame_from_str("json.set"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_json_set_with_invalid_json_returns_error() {
+    let command = vec![
+        get_frame_from_str("json.set"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$"),
+        get_frame_from_str("{\"baz\":"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_json_set_with_invalid_path_returns_error() {
+    let command = vec![
+        get_frame_from_str("json.set"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+        get_frame_from_str("1"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_json_set_returns_json_set() {
+    let command = vec![
+        get_frame_from_str("json.set"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$.baz"),
+        get_frame_from_str("{\"qux\":[1,2]}"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::JsonSet(JsonSet {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            path: Path::parse("$.baz").unwrap(),
+            value: json!({"qux": [1, 2]}),
+        })
+    );
+}
+
+#[test]
+fn parse_given_json_get_without_path_returns_json_get_with_root_path() {
+    let command = vec![
+        get_frame_from_str("json.get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::JsonGet(JsonGet {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            path: Path::parse("$").unwrap(),
+            native: false,
+        })
+    );
+}
+
+#[test]
+fn parse_given_json_get_as_native_returns_json_get() {
+    let command = vec![
+        get_frame_from_str("json.get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$.baz[0]"),
+        get_frame_from_str("as"),
+        get_frame_from_str("native"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::JsonGet(JsonGet {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            path: Path::parse("$.baz[0]").unwrap(),
+            native: true,
+        })
+    );
+}
+
+#[test]
+fn parse_given_json_get_with_invalid_format_returns_error() {
+    let command = vec![
+        get_frame_from_str("json.get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$"),
+        get_frame_from_str("as"),
+        get_frame_from_str("xml"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_json_del_returns_json_del() {
+    let command = vec![
+        get_frame_from_str("json.del"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$.baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::JsonDel(JsonDel {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            path: Path::parse("$.baz").unwrap(),
+        })
+    );
+}
+
+#[test]
+fn parse_given_json_numincrby_with_invalid_number_returns_error() {
+    let command = vec![
+        get_frame_from_str("json.numincrby"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$.baz"),
+        get_frame_from_str("qux"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_json_numincrby_returns_json_incr_by() {
+    let command = vec![
+        get_frame_from_str("json.numincrby"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("$.baz"),
+        get_frame_from_str("-2"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::JsonIncrBy(JsonIncrBy {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            path: Path::parse("$.baz").unwrap(),
+            by: Number::from(-2),
+        })
+    );
+}
+
+#[test]
+fn parse_given_pfadd_without_key_returns_error() {
+    let command = vec![get_frame_from_str("pfadd"), get_frame_from_str("foo")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_pfadd_returns_pfadd() {
+    let command = vec![
+        get_frame_from_str("pfadd"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("a"),
+        get_frame_from_str("b"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::PfAdd(PfAdd {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            elements: vec![Bytes::from("a"), Bytes::from("b")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_pfcount_without_keys_returns_error() {
+    let command = vec![get_frame_from_str("pfcount"), get_frame_from_str("foo")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_pfcount_returns_pfcount() {
+    let command = vec![
+        get_frame_from_str("pfcount"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::PfCount(PfCount {
+            keyspace: Bytes::from("foo"),
+            keys: vec![Bytes::from("bar"), Bytes::from("baz")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_pfmerge_returns_pfmerge() {
+    let command = vec![
+        get_frame_from_str("pfmerge"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::PfMerge(PfMerge {
+            keyspace: Bytes::from("foo"),
+            destination: Bytes::from("bar"),
+            sources: vec![Bytes::from("baz")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_bf_reserve_with_invalid_error_rate_returns_error() {
+    let command = vec![
+        get_frame_from_str("bf.reserve"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("1000"),
+        get_frame_from_str("1.5"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bf_reserve_with_zero_capacity_returns_error() {
+    let command = vec![
+        get_frame_from_str("bf.reserve"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("0"),
+        get_frame_from_str("0.01"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bf_reserve_with_too_large_filter_returns_error() {
+    let command = vec![
+        get_frame_from_str("bf.reserve"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("18446744073709551615"),
+        get_frame_from_str("0.0001"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err());
+
+    let command = vec![
+        get_frame_from_str("bf.reserve"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("1000000000"),
+        get_frame_from_str("0.0000001"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err());
+}
+
+#[test]
+fn parse_given_bf_reserve_returns_bf_reserve() {
+    let command = vec![
+        get_frame_from_str("bf.reserve"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("1000"),
+        get_frame_from_str("0.01"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BfReserve(BfReserve {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            capacity: 1000,
+            error_rate: 0.01,
+        })
+    );
+}
+
+#[test]
+fn parse_given_bf_add_with_multiple_items_returns_error() {
+    let command = vec![
+        get_frame_from_str("bf.add"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("a"),
+        get_frame_from_str("b"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bf_madd_returns_bf_madd() {
+    let command = vec![
+        get_frame_from_str("bf.madd"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("a"),
+        get_frame_from_str("b"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BfMAdd(BfAdd {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            items: vec![Bytes::from("a"), Bytes::from("b")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_bf_exists_without_item_returns_error() {
+    let command = vec![
+        get_frame_from_str("bf.exists"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bf_exists_returns_bf_exists() {
+    let command = vec![
+        get_frame_from_str("bf.exists"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("a"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BfExists(BfExists {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            items: vec![Bytes::from("a")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_setbit_with_invalid_bit_returns_error() {
+    let command = vec![
+        get_frame_from_str("setbit"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("7"),
+        get_frame_from_str("2"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_setbit_with_out_of_range_offset_returns_error() {
+    let command = vec![
+        get_frame_from_str("setbit"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("4294967296"),
+        get_frame_from_str("1"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_setbit_returns_setbit() {
+    let command = vec![
+        get_frame_from_str("setbit"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("7"),
+        get_frame_from_str("1"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::SetBit(SetBit {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            offset: 7,
+            value: true,
+        })
+    );
+}
+
+#[test]
+fn parse_given_getbit_with_negative_offset_returns_error() {
+    let command = vec![
+        get_frame_from_str("getbit"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("-1"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_getbit_returns_getbit() {
+    let command = vec![
+        get_frame_from_str("getbit"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("7"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::GetBit(GetBit {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            offset: 7,
+        })
+    );
+}
+
+#[test]
+fn parse_given_bitcount_with_start_and_without_end_returns_error() {
+    let command = vec![
+        get_frame_from_str("bitcount"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("0"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bitcount_with_range_returns_bitcount() {
+    let command = vec![
+        get_frame_from_str("bitcount"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("1"),
+        get_frame_from_str("-1"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BitCount(BitCount {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            range: Some((1, -1)),
+        })
+    );
+}
+
+#[test]
+fn parse_given_bitpos_with_start_returns_bitpos() {
+    let command = vec![
+        get_frame_from_str("bitpos"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("0"),
+        get_frame_from_str("2"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BitPos(BitPos {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            bit: false,
+            start: Some(2),
+            end: None,
+        })
+    );
+}
+
+#[test]
+fn parse_given_bitop_with_invalid_operation_returns_error() {
+    let command = vec![
+        get_frame_from_str("bitop"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("nand"),
+        get_frame_from_str("dest"),
+        get_frame_from_str("bar"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bitop_not_with_multiple_keys_returns_error() {
+    let command = vec![
+        get_frame_from_str("bitop"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("not"),
+        get_frame_from_str("dest"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bitop_returns_bitop() {
+    let command = vec![
+        get_frame_from_str("bitop"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("AND"),
+        get_frame_from_str("dest"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BitOp(BitOp {
+            keyspace: Bytes::from("foo"),
+            operation: BitOperation::And,
+            destination: Bytes::from("dest"),
+            keys: vec![Bytes::from("bar"), Bytes::from("baz")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_append_without_value_returns_error() {
+    let command = vec![
+        get_frame_from_str("append"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_append_returns_append() {
+    let command = vec![
+        get_frame_from_str("append"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Append(Append {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            value: Bytes::from("baz"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_getrange_with_invalid_end_returns_error() {
+    let command = vec![
+        get_frame_from_str("getrange"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("0"),
+        get_frame_from_str("baz"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_getrange_returns_getrange() {
+    let command = vec![
+        get_frame_from_str("getrange"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("0"),
+        get_frame_from_str("-1"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::GetRange(GetRange {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            start: 0,
+            end: -1,
+        })
+    );
+}
+
+#[test]
+fn parse_given_setrange_with_negative_offset_returns_error() {
+    let command = vec![
+        get_frame_from_str("setrange"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("-1"),
+        get_frame_from_str("baz"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_setrange_returns_setrange() {
+    let command = vec![
+        get_frame_from_str("setrange"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("6"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::SetRange(SetRange {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            offset: 6,
+            value: Bytes::from("baz"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_strlen_returns_strlen() {
+    let command = vec![
+        get_frame_from_str("strlen"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::StrLen(StrLen {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_transaction_commands_returns_transaction_commands() {
+    let multi = vec![get_frame_from_str("multi")];
+    let exec = vec![get_frame_from_str("EXEC")];
+    let discard = vec![get_frame_from_str("discard")];
+
+    assert_eq!(parse(Frame::Array(multi)).unwrap(), Command::Multi);
+    assert_eq!(parse(Frame::Array(exec)).unwrap(), Command::Exec);
+    assert_eq!(parse(Frame::Array(discard)).unwrap(), Command::Discard);
+}
+
+#[test]
+fn keyspace_given_keyspace_command_returns_keyspace() {
+    let command = vec![
+        get_frame_from_str("get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap().keyspace(),
+        Some(Bytes::from("foo"))
+    );
+    assert_eq!(Command::Multi.keyspace(), None);
+}
+
+#[test]
+fn parse_given_get_with_version_returns_get_with_version() {
+    let command = vec![
+        get_frame_from_str("get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("WITH"),
+        get_frame_from_str("VERSION"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Get(Get {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            with_version: true,
+        })
+    );
+}
+
+#[test]
+fn parse_given_get_with_unknown_option_returns_error() {
+    let command = vec![
+        get_frame_from_str("get"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("with"),
+        get_frame_from_str("ttl"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_set_if_version_returns_set_if_version() {
+    let command = vec![
+        get_frame_from_str("set"),
+        get_frame_from_str("my_keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("if"),
+        get_frame_from_str("version"),
+        get_frame_from_str("42"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Set(Set {
+            keyspace: Bytes::from("my_keyspace"),
+            key: Bytes::from("foo"),
+            value: Bytes::from("bar"),
+            if_not_exists: false,
+            expiry: None,
+            if_exists: false,
+            if_version: Some(42),
+            if_value_equals: None
+        })
+    );
+}
+
+#[test]
+fn parse_given_set_if_version_and_if_exists_returns_error() {
+    let command = vec![
+        get_frame_from_str("set"),
+        get_frame_from_str("my_keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("if"),
+        get_frame_from_str("version"),
+        get_frame_from_str("42"),
+        get_frame_from_str("if"),
+        get_frame_from_str("exists"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_set_if_value_equals_returns_set_if_value_equals() {
+    let command = vec![
+        get_frame_from_str("set"),
+        get_frame_from_str("my_keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("IF"),
+        get_frame_from_str("VALUE"),
+        get_frame_from_str("EQUALS"),
+        get_frame_from_str("baz"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Set(Set {
+            keyspace: Bytes::from("my_keyspace"),
+            key: Bytes::from("foo"),
+            value: Bytes::from("bar"),
+            if_not_exists: false,
+            expiry: None,
+            if_exists: false,
+            if_version: None,
+            if_value_equals: Some(Bytes::from("baz"))
+        })
+    );
+}
+
+#[test]
+fn parse_given_set_if_value_without_equals_returns_error() {
+    let command = vec![
+        get_frame_from_str("set"),
+        get_frame_from_str("my_keyspace"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("if"),
+        get_frame_from_str("value"),
+        get_frame_from_str("baz"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_del_if_value_equals_returns_del_if_value_equals() {
+    let command = vec![
+        get_frame_from_str("del"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("if"),
+        get_frame_from_str("value"),
+        get_frame_from_str("equals"),
+        get_frame_from_str("token"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Del(Del {
+            keyspace: Bytes::from("foo"),
+            key: Bytes::from("bar"),
+            if_value_equals: Some(Bytes::from("token")),
+        })
+    );
+}
+
+#[test]
+fn parse_given_del_with_unknown_condition_returns_error() {
+    let command = vec![
+        get_frame_from_str("del"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+        get_frame_from_str("if"),
+        get_frame_from_str("exists"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_subscribe_without_channels_returns_error() {
+    let command = vec![get_frame_from_str("subscribe")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_psubscribe_returns_psubscribe() {
+    let command = vec![
+        get_frame_from_str("psubscribe"),
+        get_frame_from_str("news.*"),
+        get_frame_from_str("weather.*"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::PSubscribe(Subscribe {
+            channels: vec![Bytes::from("news.*"), Bytes::from("weather.*")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_unsubscribe_without_channels_returns_unsubscribe_all() {
+    let command = vec![get_frame_from_str("unsubscribe")];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Unsubscribe(Unsubscribe { channels: vec![] })
+    );
+}
+
+#[test]
+fn parse_given_publish_returns_publish() {
+    let command = vec![
+        get_frame_from_str("publish"),
+        get_frame_from_str("news"),
+        get_frame_from_str("hello"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Publish(Publish {
+            channel: Bytes::from("news"),
+            message: Bytes::from("hello"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_publish_with_extra_args_returns_error() {
+    let command = vec![
+        get_frame_from_str("publish"),
+        get_frame_from_str("news"),
+        get_frame_from_str("hello"),
+        get_frame_from_str("world"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_watch_events_with_match_returns_watch_events() {
+    let command = vec![
+        get_frame_from_str("watch"),
+        get_frame_from_str("events"),
+        get_frame_from_str("users"),
+        get_frame_from_str("match"),
+        get_frame_from_str("session:*"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::WatchEvents(WatchEvents {
+            keyspace: Bytes::from("users"),
+            pattern: Some(Bytes::from("session:*")),
+        })
+    );
+}
+
+#[test]
+fn parse_given_watch_without_events_returns_error() {
+    let command = vec![get_frame_from_str("watch"), get_frame_from_str("users")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_unwatch_events_returns_unwatch_events() {
+    let command = vec![
+        get_frame_from_str("unwatch"),
+        get_frame_from_str("EVENTS"),
+        get_frame_from_str("users"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::UnwatchEvents(UnwatchEvents {
+            keyspace: Bytes::from("users"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_mget_returns_mget() {
+    let command = vec![
+        get_frame_from_str("mget"),
+        get_frame_from_str("users"),
+        get_frame_from_str("foo"),
+        get_frame_from_str("bar"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::MGet(MGet {
+            keyspace: Bytes::from("users"),
+            keys: vec![Bytes::from("foo"), Bytes::from("bar")],
+        })
+    );
+}
+
+#[test]
+fn parse_given_mget_without_keys_returns_error() {
+    let command = vec![get_frame_from_str("mget"), get_frame_from_str("users")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_tracking_on_returns_default_mode() {
+    let command = vec![get_frame_from_str("tracking"), get_frame_from_str("ON")];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Tracking(Tracking {
+            mode: Some(TrackingMode::Default),
+        })
+    );
+}
+
+#[test]
+fn parse_given_tracking_on_bcast_with_prefixes_returns_broadcast_mode() {
+    let command = vec![
+        get_frame_from_str("tracking"),
+        get_frame_from_str("on"),
+        get_frame_from_str("bcast"),
+        get_frame_from_str("prefix"),
+        get_frame_from_str("user:"),
+        get_frame_from_str("prefix"),
+        get_frame_from_str("order:"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Tracking(Tracking {
+            mode: Some(TrackingMode::Broadcast(vec![
+                Bytes::from("user:"),
+                Bytes::from("order:"),
+            ])),
+        })
+    );
+}
+
+#[test]
+fn parse_given_tracking_off_returns_no_mode() {
+    let command = vec![get_frame_from_str("tracking"), get_frame_from_str("off")];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Tracking(Tracking { mode: None })
+    );
+}
+
+#[test]
+fn parse_given_tracking_prefix_without_bcast_returns_error() {
+    let command = vec![
+        get_frame_from_str("tracking"),
+        get_frame_from_str("on"),
+        get_frame_from_str("prefix"),
+        get_frame_from_str("user:"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_auth_returns_auth() {
+    let command = vec![
+        get_frame_from_str("auth"),
+        get_frame_from_str("alice"),
+        get_frame_from_str("secret"),
+    ];
+
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Auth(Auth {
+            username: "alice".to_string(),
+            password: Bytes::from("secret"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_auth_without_password_returns_error() {
+    let command = vec![get_frame_from_str("auth"), get_frame_from_str("alice")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_client_list_returns_client_list() {
+    let command = vec![get_frame_from_str("client"), get_frame_from_str("LIST")];
+    assert_eq!(parse(Frame::Array(command)).unwrap(), Command::ClientList);
+}
+
+#[test]
+fn parse_given_client_kill_returns_id_or_addr() {
+    let command = vec![
+        get_frame_from_str("client"),
+        get_frame_from_str("kill"),
+        get_frame_from_str("42"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::ClientKill(ClientKill::Id(42))
+    );
+
+    let command = vec![
+        get_frame_from_str("client"),
+        get_frame_from_str("kill"),
+        get_frame_from_str("127.0.0.1:5000"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::ClientKill(ClientKill::Addr("127.0.0.1:5000".to_string()))
+    );
+}
+
+#[test]
+fn parse_given_client_setname_returns_client_setname() {
+    let command = vec![
+        get_frame_from_str("client"),
+        get_frame_from_str("setname"),
+        get_frame_from_str("worker-1"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::ClientSetName(ClientSetName {
+            name: Bytes::from("worker-1"),
+        })
+    );
+}
+
+#[test]
+fn parse_given_client_pause_returns_client_pause() {
+    let command = vec![
+        get_frame_from_str("client"),
+        get_frame_from_str("pause"),
+        get_frame_from_str("1500"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::ClientPause(ClientPause {
+            duration: Duration::from_millis(1500),
+        })
+    );
+}
+
+#[test]
+fn parse_given_client_with_unknown_subcommand_returns_error() {
+    let command = vec![get_frame_from_str("client"), get_frame_from_str("reboot")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_info_with_section_returns_info() {
+    let command = vec![get_frame_from_str("info"), get_frame_from_str("KEYSPACES")];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Info(Info {
+            section: Some(InfoSection::Keyspaces),
+        })
+    );
+
+    let command = vec![get_frame_from_str("info")];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::Info(Info { section: None })
+    );
+}
+
+#[test]
+fn parse_given_info_with_unknown_section_returns_error() {
+    let command = vec![get_frame_from_str("info"), get_frame_from_str("cpu")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_slowlog_get_returns_count_or_default() {
+    let command = vec![
+        get_frame_from_str("slowlog"),
+        get_frame_from_str("get"),
+        get_frame_from_str("5"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::SlowLogGet(SlowLogGet { count: 5 })
+    );
+
+    let command = vec![get_frame_from_str("slowlog"), get_frame_from_str("GET")];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::SlowLogGet(SlowLogGet { count: 10 })
+    );
+}
+
+#[test]
+fn parse_given_slowlog_len_and_reset_returns_command() {
+    let command = vec![get_frame_from_str("slowlog"), get_frame_from_str("len")];
+    assert_eq!(parse(Frame::Array(command)).unwrap(), Command::SlowLogLen);
+
+    let command = vec![get_frame_from_str("slowlog"), get_frame_from_str("reset")];
+    assert_eq!(parse(Frame::Array(command)).unwrap(), Command::SlowLogReset);
+
+    let command = vec![
+        get_frame_from_str("slowlog"),
+        get_frame_from_str("len"),
+        get_frame_from_str("1"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_monitor_returns_monitor() {
+    let command = vec![get_frame_from_str("monitor")];
+    assert_eq!(parse(Frame::Array(command)).unwrap(), Command::Monitor);
+
+    let command = vec![get_frame_from_str("monitor"), get_frame_from_str("all")];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_memory_usage_and_stats_returns_command() {
+    let command = vec![
+        get_frame_from_str("memory"),
+        get_frame_from_str("usage"),
+        get_frame_from_str("ks"),
+        get_frame_from_str("key"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::MemoryUsage(MemoryUsage {
+            keyspace: Bytes::from("ks"),
+            key: Bytes::from("key"),
+        })
+    );
+
+    let command = vec![get_frame_from_str("memory"), get_frame_from_str("STATS")];
+    assert_eq!(parse(Frame::Array(command)).unwrap(), Command::MemoryStats);
+
+    let command = vec![
+        get_frame_from_str("memory"),
+        get_frame_from_str("usage"),
+        get_frame_from_str("ks"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_bigkeys_with_limit_returns_bigkeys() {
+    let command = vec![
+        get_frame_from_str("bigkeys"),
+        get_frame_from_str("ks"),
+        get_frame_from_str("limit"),
+        get_frame_from_str("3"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BigKeys(BigKeys {
+            keyspace: Bytes::from("ks"),
+            limit: 3,
+        })
+    );
+
+    let command = vec![get_frame_from_str("bigkeys"), get_frame_from_str("ks")];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::BigKeys(BigKeys {
+            keyspace: Bytes::from("ks"),
+            limit: 10,
+        })
+    );
+
+    let command = vec![
+        get_frame_from_str("bigkeys"),
+        get_frame_from_str("ks"),
+        get_frame_from_str("count"),
+        get_frame_from_str("3"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
+
+#[test]
+fn parse_given_hotkeys_with_limit_returns_hotkeys() {
+    let command = vec![
+        get_frame_from_str("hotkeys"),
+        get_frame_from_str("ks"),
+        get_frame_from_str("LIMIT"),
+        get_frame_from_str("5"),
+    ];
+    assert_eq!(
+        parse(Frame::Array(command)).unwrap(),
+        Command::HotKeys(HotKeys {
+            keyspace: Bytes::from("ks"),
+            limit: 5,
+        })
+    );
+
+    let command = vec![
+        get_frame_from_str("hotkeys"),
+        get_frame_from_str("ks"),
+        get_frame_from_str("limit"),
+    ];
+    assert!(parse(Frame::Array(command)).is_err())
+}
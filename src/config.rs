@@ -1,14 +1,31 @@
+use crate::acl::{Acl, AclError, User};
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::net::{AddrParseError, IpAddr, Ipv4Addr};
 use std::num::ParseIntError;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 const PORT_LABEL: &str = "port";
 const MAX_MEMORY_LABEL: &str = "max_memory";
 const CONNECTION_BUFFER_SIZE_LABEL: &str = "connection_buffer_size";
 const BIND_LABEL: &str = "bind";
+const SUBSCRIBER_BUFFER_SIZE_LABEL: &str = "subscriber_buffer_size";
+const USER_LABEL: &str = "user";
+const TLS_PORT_LABEL: &str = "tls_port";
+const TLS_CERT_FILE_LABEL: &str = "tls_cert_file";
+const TLS_KEY_FILE_LABEL: &str = "tls_key_file";
+const TLS_CA_FILE_LABEL: &str = "tls_ca_file";
+const UNIX_SOCKET_LABEL: &str = "unix_socket";
+const UNIX_SOCKET_PERM_LABEL: &str = "unix_socket_perm";
+const MAX_CLIENTS_LABEL: &str = "max_clients";
+const IDLE_TIMEOUT_LABEL: &str = "idle_timeout";
+const TCP_KEEPALIVE_LABEL: &str = "tcp_keepalive";
+const METRICS_PORT_LABEL: &str = "metrics_port";
+const SLOWLOG_THRESHOLD_US_LABEL: &str = "slowlog_threshold_us";
+const SLOWLOG_MAX_LEN_LABEL: &str = "slowlog_max_len";
 
 #[derive(Debug)]
 pub struct ServerConfig {
@@ -16,6 +33,20 @@ pub struct ServerConfig {
     max_memory: u64,
     connection_buffer_size: usize,
     bind: IpAddr,
+    subscriber_buffer_size: usize,
+    acl: Arc<Acl>,
+    tls_port: Option<u16>,
+    tls_cert_file: Option<String>,
+    tls_key_file: Option<String>,
+    tls_ca_file: Option<String>,
+    unix_socket: Option<String>,
+    unix_socket_perm: u32,
+    max_clients: usize,
+    idle_timeout: Duration,
+    tcp_keepalive: Duration,
+    metrics_port: Option<u16>,
+    slowlog_threshold: Duration,
+    slowlog_max_len: usize,
 }
 
 #[derive(Debug, Error)]
@@ -34,6 +65,12 @@ pub enum ServerConfigError {
 
     #[error(transparent)]
     AddrParseError(#[from] AddrParseError),
+
+    #[error(transparent)]
+    Acl(#[from] AclError),
+
+    #[error("directive '{0}' requires '{1}' to be set")]
+    MissingDirective(String, String),
 }
 
 impl ServerConfig {
@@ -48,7 +85,22 @@ impl ServerConfig {
             max_memory: 0,
             connection_buffer_size: 4096,
             bind: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+            subscriber_buffer_size: 1024,
+            acl: Arc::new(Acl::new()),
+            tls_port: None,
+            tls_cert_file: None,
+            tls_key_file: None,
+            tls_ca_file: None,
+            unix_socket: None,
+            unix_socket_perm: 0o700,
+            max_clients: 10000,
+            idle_timeout: Duration::ZERO,
+            tcp_keepalive: Duration::from_secs(300),
+            metrics_port: None,
+            slowlog_threshold: Duration::from_millis(10),
+            slowlog_max_len: 128,
         };
+        let mut acl = Acl::new();
         for maybe_line in reader.lines() {
             let line = &maybe_line?;
             if line.trim().starts_with('#') || line.trim().is_empty() {
@@ -88,6 +140,55 @@ impl ServerConfig {
                     let bind = IpAddr::from_str(tokens[1])?;
                     config.bind = bind
                 }
+                SUBSCRIBER_BUFFER_SIZE_LABEL => {
+                    let subscriber_buffer_size = tokens[1].parse::<usize>()?;
+                    if subscriber_buffer_size == 0 {
+                        return Err(ServerConfigError::InvalidFormat(line.clone()));
+                    }
+                    config.subscriber_buffer_size = subscriber_buffer_size;
+                }
+                USER_LABEL => {
+                    acl.add_user(User::parse(tokens[1])?)?;
+                }
+                TLS_PORT_LABEL => {
+                    let tls_port = tokens[1].parse::<u16>()?;
+                    config.tls_port = Some(tls_port);
+                }
+                TLS_CERT_FILE_LABEL => config.tls_cert_file = Some(tokens[1].to_string()),
+                TLS_KEY_FILE_LABEL => config.tls_key_file = Some(tokens[1].to_string()),
+                TLS_CA_FILE_LABEL => config.tls_ca_file = Some(tokens[1].to_string()),
+                UNIX_SOCKET_LABEL => config.unix_socket = Some(tokens[1].to_string()),
+                UNIX_SOCKET_PERM_LABEL => {
+                    let unix_socket_perm = u32::from_str_radix(tokens[1], 8)?;
+                    if unix_socket_perm > 0o777 {
+                        return Err(ServerConfigError::InvalidFormat(line.clone()));
+                    }
+                    config.unix_socket_perm = unix_socket_perm;
+                }
+                MAX_CLIENTS_LABEL => {
+                    let max_clients = tokens[1].parse::<usize>()?;
+                    config.max_clients = max_clients;
+                }
+                IDLE_TIMEOUT_LABEL => {
+                    let idle_timeout = tokens[1].parse::<u64>()?;
+                    config.idle_timeout = Duration::from_secs(idle_timeout);
+                }
+                TCP_KEEPALIVE_LABEL => {
+                    let tcp_keepalive = tokens[1].parse::<u64>()?;
+                    config.tcp_keepalive = Duration::from_secs(tcp_keepalive);
+                }
+                METRICS_PORT_LABEL => {
+                    let metrics_port = tokens[1].parse::<u16>()?;
+                    config.metrics_port = Some(metrics_port);
+                }
+                SLOWLOG_THRESHOLD_US_LABEL => {
+                    let slowlog_threshold = tokens[1].parse::<u64>()?;
+                    config.slowlog_threshold = Duration::from_micros(slowlog_threshold);
+                }
+                SLOWLOG_MAX_LEN_LABEL => {
+                    let slowlog_max_len = tokens[1].parse::<usize>()?;
+                    config.slowlog_max_len = slowlog_max_len;
+                }
                 _ => {
                     return Err(ServerConfigError::UnknownDirective(
                         tokens[0].to_string(),
@@ -97,6 +198,30 @@ impl ServerConfig {
             }
         }
 
+        if config.tls_port.is_some() {
+            if config.tls_cert_file.is_none() {
+                return Err(ServerConfigError::MissingDirective(
+                    TLS_PORT_LABEL.to_string(),
+                    TLS_CERT_FILE_LABEL.to_string(),
+                ));
+            }
+            if config.tls_key_file.is_none() {
+                return Err(ServerConfigError::MissingDirective(
+                    TLS_PORT_LABEL.to_string(),
+                    TLS_KEY_FILE_LABEL.to_string(),
+                ));
+            }
+        }
+
+        // port 0 turns off tcp, which leaves nothing to connect to without a unix socket
+        if config.port == 0 && config.unix_socket.is_none() {
+            return Err(ServerConfigError::MissingDirective(
+                PORT_LABEL.to_string(),
+                UNIX_SOCKET_LABEL.to_string(),
+            ));
+        }
+
+        config.acl = Arc::new(acl);
         Ok(config)
     }
 
@@ -115,4 +240,60 @@ impl ServerConfig {
     pub fn bind(&self) -> String {
         self.bind.to_string()
     }
+
+    pub fn subscriber_buffer_size(&self) -> usize {
+        self.subscriber_buffer_size
+    }
+
+    pub fn acl(&self) -> Arc<Acl> {
+        self.acl.clone()
+    }
+
+    pub fn tls_port(&self) -> Option<u16> {
+        self.tls_port
+    }
+
+    pub fn tls_cert_file(&self) -> Option<&str> {
+        self.tls_cert_file.as_deref()
+    }
+
+    pub fn tls_key_file(&self) -> Option<&str> {
+        self.tls_key_file.as_deref()
+    }
+
+    pub fn tls_ca_file(&self) -> Option<&str> {
+        self.tls_ca_file.as_deref()
+    }
+
+    pub fn unix_socket(&self) -> Option<&str> {
+        self.unix_socket.as_deref()
+    }
+
+    pub fn unix_socket_perm(&self) -> u32 {
+        self.unix_socket_perm
+    }
+
+    pub fn max_clients(&self) -> usize {
+        self.max_clients
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    pub fn tcp_keepalive(&self) -> Duration {
+        self.tcp_keepalive
+    }
+
+    pub fn metrics_port(&self) -> Option<u16> {
+        self.metrics_port
+    }
+
+    pub fn slowlog_threshold(&self) -> Duration {
+        self.slowlog_threshold
+    }
+
+    pub fn slowlog_max_len(&self) -> usize {
+        self.slowlog_max_len
+    }
 }
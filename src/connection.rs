@@ -43,13 +43,25 @@ where
         }
     }
 
+    /// Size of the buffer the connection reads into, it grows to fit the largest frame.
+    pub fn buffer_capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
     pub async fn read_frame(&mut self) -> Result<Option<Frame>, ConnectionError> {
         loop {
             if let Some(frame) = self.parse_frame()? {
                 return Ok(Some(frame));
             }
 
-            if self.stream.read_buf(&mut self.buf).await? == 0 {
+            let read = match self.stream.read_buf(&mut self.buf).await {
+                Ok(read) => read,
+                // tls clients often close the socket without a close_notify, which is
+                // only worth reporting when it cuts a frame short
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => 0,
+                Err(e) => return Err(e.into()),
+            };
+            if read == 0 {
                 if self.buf.is_empty() {
                     return Ok(None);
                 } else {
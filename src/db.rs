@@ -1,17 +1,36 @@
 use crate::{
-    command::{Command, Count, Create, Del, Drop, Get, Set, Ttl},
+    bitmap::{self, BitOperation},
+    bloom::{self, BloomFilter},
+    clients::Clients,
+    command::{
+        Alter, BigKeys, Command, CopyKeyspace, Create, Drop, Info, InfoSection, KeyspaceOption,
+        Publish, RenameKeyspace,
+    },
     connection::ConnectionError,
     frame::Frame,
+    hll::HyperLogLog,
+    hotkeys::HotKeys,
+    json::{self, JsonError, Path},
+    monitor::Monitors,
+    pubsub::{KeyspaceEvent, KeyspaceEvents, PubSub, Subscriber},
+    slowlog::SlowLog,
+    stats::{KeyspaceSnapshot, KeyspaceStats, ServerStats},
+    store::{entry_size, Store, ValueMut},
+    tracking::Tracker,
 };
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use crossbeam::sync::WaitGroup;
-use parking_lot::{Mutex, RwLock};
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use serde_json::Number;
 use std::{
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    ops::Add,
     str::{self, Utf8Error},
     time::Duration,
 };
 use std::{
+    sync::atomic::{AtomicU64, Ordering},
     sync::Arc,
     time::{Instant, SystemTime, SystemTimeError, UNIX_EPOCH},
 };
@@ -22,12 +41,26 @@ use tracing::{debug, error};
 
 static EXPIRING_EVICTOR_SAMPLE_SIZE: u8 = 5;
 static MAX_MEMORY_EVICTOR_SAMPLE_SIZE: u8 = 3;
+// number of keys looked at per lock of the store when scanning a keyspace
+static SCAN_BATCH_SIZE: usize = 1024;
+// versions are drawn from a single counter so a key that is deleted and
+// created again never reuses a version it had before
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+pub enum Data {
+    String(Bytes),
+    Json(serde_json::Value),
+    HyperLogLog(HyperLogLog),
+    Bloom(BloomFilter),
+}
+
+#[derive(Debug, Clone)]
 pub struct Value {
-    data: Bytes,
+    data: Data,
     last_accessed: Instant,
     expire_at: Option<u64>,
+    version: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -37,20 +70,49 @@ pub enum Evictor {
     Lru,
 }
 
+/// Limits and defaults of a keyspace, a limit of 0 is unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyspaceOptions {
+    max_keys: u64,
+    max_memory: u64,
+    default_expire_after: u64,
+    max_value_size: u64,
+    read_only: bool,
+}
+
 #[derive(Debug)]
 pub struct Keyspace {
-    store: Arc<Mutex<HashMap<Bytes, Value>>>,
+    store: Arc<Mutex<Store>>,
     expiring: Arc<Mutex<HashMap<Bytes, u64>>>,
+    // number of keys of the store with an expiry
+    expiring_keys: Arc<AtomicU64>,
     evictor: Evictor,
+    options: Arc<RwLock<KeyspaceOptions>>,
     wg: WaitGroup,
     done: broadcast::Receiver<()>,
     drop: broadcast::Sender<()>,
+    // dropped to stop the running max memory evictor when the evictor is replaced
+    stop_max_memory_evictor: Option<broadcast::Sender<()>>,
     evict: broadcast::Receiver<()>,
+    events: Arc<KeyspaceEvents>,
+    stats: Arc<KeyspaceStats>,
+    hotkeys: Mutex<HotKeys>,
+}
+
+pub struct KeyspaceHandle<'a> {
+    keyspace: &'a Keyspace,
+    store: MutexGuard<'a, Store>,
 }
 
 #[derive(Debug)]
 pub struct Db {
     keyspaces: RwLock<HashMap<Bytes, Keyspace>>,
+    pubsub: PubSub,
+    tracker: Arc<Tracker>,
+    clients: Clients,
+    stats: ServerStats,
+    slowlog: SlowLog,
+    monitors: Monitors,
     done: broadcast::Receiver<()>,
     wg: WaitGroup,
     evict: broadcast::Receiver<()>,
@@ -72,6 +134,63 @@ pub enum ExecuteCommandError {
 
     #[error(transparent)]
     SystemTimeError(#[from] SystemTimeError),
+
+    #[error("operation against a key holding the wrong kind of value")]
+    WrongType,
+
+    #[error(transparent)]
+    Json(#[from] JsonError),
+
+    #[error("key already exists")]
+    KeyExists,
+
+    #[error("'{0}' command is not allowed in a transaction")]
+    NotAllowedInTransaction(String),
+
+    #[error("version conflict, current version is {0}")]
+    VersionConflict(u64),
+
+    #[error("'{0}' command is only supported on a connection")]
+    ConnectionOnly(String),
+
+    #[error("transaction already started")]
+    NestedTransaction,
+
+    #[error("no transaction started")]
+    NoTransaction,
+
+    #[error("transaction discarded because of previous errors")]
+    TransactionAborted,
+
+    #[error("authentication required")]
+    AuthRequired,
+
+    #[error("authentication is not enabled")]
+    AuthNotEnabled,
+
+    #[error("invalid username or password")]
+    InvalidCredentials,
+
+    #[error("permission denied, user '{0}' is not allowed to run '{1}'")]
+    PermissionDenied(String, String),
+
+    #[error("max number of clients reached")]
+    MaxClientsReached,
+
+    #[error("no such client")]
+    NoSuchClient,
+
+    #[error("keyspace is read only")]
+    ReadOnly,
+
+    #[error("max keys of {0} reached")]
+    MaxKeysReached(u64),
+
+    #[error("max memory of {0} bytes reached")]
+    MaxMemoryReached(u64),
+
+    #[error("value is larger than the max value size of {0} bytes")]
+    ValueTooLarge(u64),
 }
 
 impl Db {
@@ -79,9 +198,17 @@ impl Db {
         done: broadcast::Receiver<()>,
         wg: WaitGroup,
         evict: broadcast::Receiver<()>,
+        max_memory: u64,
+        slowlog: SlowLog,
     ) -> Self {
         Db {
             keyspaces: RwLock::new(HashMap::new()),
+            pubsub: PubSub::new(),
+            tracker: Arc::new(Tracker::new()),
+            clients: Clients::new(),
+            stats: ServerStats::new(max_memory),
+            slowlog,
+            monitors: Monitors::new(),
             done,
             wg,
             evict,
@@ -91,14 +218,309 @@ impl Db {
     pub async fn execute(&self, command: Command) -> Result<Frame, ExecuteCommandError> {
         match command {
             Command::Create(cmd) => self.exec_create(&cmd).await,
+            Command::Alter(cmd) => self.exec_alter(&cmd),
+            Command::RenameKeyspace(cmd) => self.exec_rename_keyspace(&cmd),
+            Command::CopyKeyspace(cmd) => self.exec_copy_keyspace(&cmd),
             Command::Drop(cmd) => self.exec_drop(&cmd),
             Command::Keyspaces => self.exec_keyspaces(),
-            Command::Set(cmd) => self.exec_set(&cmd),
             Command::Ping => Ok(Frame::String(Bytes::from_static(b"PONG"))),
-            Command::Get(cmd) => self.exec_get(&cmd),
-            Command::Del(cmd) => self.exec_del(&cmd),
-            Command::Count(cmd) => self.exec_count(&cmd),
-            Command::Ttl(cmd) => self.exec_ttl(&cmd),
+            Command::Publish(cmd) => self.exec_publish(&cmd),
+            Command::Info(cmd) => self.exec_info(&cmd),
+            Command::SlowLogGet(cmd) => Ok(self.slowlog.get(cmd.count())),
+            Command::SlowLogLen => Ok(Frame::Integer(self.slowlog.len() as i64)),
+            Command::SlowLogReset => {
+                self.slowlog.reset();
+                Ok(Frame::Boolean(true))
+            }
+            Command::MemoryStats => self.exec_memory_stats().await,
+            Command::BigKeys(cmd) => self.exec_bigkeys(&cmd).await,
+            Command::Multi
+            | Command::Exec
+            | Command::Discard
+            | Command::Subscribe(_)
+            | Command::PSubscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::WatchEvents(_)
+            | Command::UnwatchEvents(_)
+            | Command::Tracking(_)
+            | Command::Auth(_)
+            | Command::ClientList
+            | Command::ClientInfo
+            | Command::ClientKill(_)
+            | Command::ClientSetName(_)
+            | Command::ClientPause(_)
+            | Command::Monitor => Err(ExecuteCommandError::ConnectionOnly(
+                command.name().to_string(),
+            )),
+            command => {
+                let handle = self.keyspaces.read();
+                let keyspace = command.keyspace().expect("command is scoped to a keyspace");
+                match handle.get(&keyspace) {
+                    Some(ks) => ks.lock().execute(&command),
+                    None => Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                        str::from_utf8(&keyspace[..])?.to_string(),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Executes the commands of a transaction atomically. Every keyspace involved is
+    /// locked upfront in sorted order so that concurrent transactions can not deadlock,
+    /// failing commands return an error in their slot without aborting the others.
+    pub fn execute_transaction(
+        &self,
+        commands: Vec<Command>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = self.keyspaces.read();
+        let mut names: Vec<Bytes> = commands.iter().filter_map(Command::keyspace).collect();
+        names.sort();
+        names.dedup();
+
+        let mut locked = HashMap::with_capacity(names.len());
+        for name in names {
+            if let Some(ks) = handle.get(&name) {
+                locked.insert(name, ks.lock());
+            }
+        }
+
+        let mut results = Vec::with_capacity(commands.len());
+        for command in commands {
+            match self.execute_locked(&handle, &mut locked, command) {
+                Ok(frame) => results.push(frame),
+                Err(e) => results.push(Frame::Error(Bytes::from(e.to_string()))),
+            }
+        }
+        Ok(Frame::Array(results))
+    }
+
+    fn execute_locked(
+        &self,
+        handle: &HashMap<Bytes, Keyspace>,
+        locked: &mut HashMap<Bytes, KeyspaceHandle>,
+        command: Command,
+    ) -> Result<Frame, ExecuteCommandError> {
+        match command {
+            Command::Keyspaces => self.list_keyspaces(handle),
+            Command::Ping => Ok(Frame::String(Bytes::from_static(b"PONG"))),
+            Command::Publish(cmd) => self.exec_publish(&cmd),
+            // scanning a keyspace gives up its lock between batches
+            Command::BigKeys(_) => Err(ExecuteCommandError::NotAllowedInTransaction(
+                command.name().to_string(),
+            )),
+            command => {
+                let keyspace = command.keyspace().ok_or_else(|| {
+                    ExecuteCommandError::NotAllowedInTransaction(command.name().to_string())
+                })?;
+                match locked.get_mut(&keyspace) {
+                    Some(ks) => ks.execute(&command),
+                    None => Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                        str::from_utf8(&keyspace[..])?.to_string(),
+                    )),
+                }
+            }
+        }
+    }
+
+    fn exec_publish(&self, cmd: &Publish) -> Result<Frame, ExecuteCommandError> {
+        let receivers = self.pubsub.publish(&cmd.channel(), &cmd.message());
+        Ok(Frame::Integer(receivers as i64))
+    }
+
+    // sums up the memory used by the keys of every keyspace, keyspaces are scanned one
+    // at a time and in batches so that commands are not held up for long
+    async fn exec_memory_stats(&self) -> Result<Frame, ExecuteCommandError> {
+        let stores = {
+            let handle = self.keyspaces.read();
+            let mut stores: Vec<(Bytes, Arc<Mutex<Store>>)> = handle
+                .iter()
+                .map(|(name, keyspace)| (name.clone(), keyspace.store.clone()))
+                .collect();
+            stores.sort_by(|(a, _), (b, _)| a.cmp(b));
+            stores
+        };
+
+        let mut total = 0;
+        let mut keyspaces = Vec::with_capacity(stores.len() * 2);
+        for (name, store) in stores {
+            let (mut keys, mut bytes) = (0, 0);
+            scan_memory_usage(&store, |_, _, size| {
+                keys += 1;
+                bytes += size as u64;
+            })
+            .await?;
+            total += bytes;
+            keyspaces.push(Frame::String(name));
+            keyspaces.push(Frame::Map(
+                [field("keys", keys), field("bytes", bytes)]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+            ));
+        }
+
+        let mut stats: Vec<Frame> = [
+            field("total_bytes", total),
+            field("used_memory", self.stats.used_memory()),
+            field("max_memory", self.stats.max_memory()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        stats.push(Frame::String(Bytes::from_static(b"keyspaces")));
+        stats.push(Frame::Map(keyspaces));
+        Ok(Frame::Map(stats))
+    }
+
+    // reports the largest keys of the keyspace, largest first
+    async fn exec_bigkeys(&self, cmd: &BigKeys) -> Result<Frame, ExecuteCommandError> {
+        let store = match self.keyspaces.read().get(&cmd.keyspace()) {
+            Some(keyspace) => keyspace.store.clone(),
+            None => {
+                return Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                    str::from_utf8(&cmd.keyspace()[..])?.to_string(),
+                ))
+            }
+        };
+
+        // min heap of the largest keys seen so far
+        let mut largest = BinaryHeap::with_capacity(cmd.limit() + 1);
+        scan_memory_usage(&store, |key, val, size| {
+            largest.push(Reverse((size, key.clone(), val.data().type_name())));
+            if largest.len() > cmd.limit() {
+                largest.pop();
+            }
+        })
+        .await?;
+
+        let keys = largest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, key, type_name))| {
+                Frame::Map(vec![
+                    Frame::String(Bytes::from_static(b"key")),
+                    Frame::String(key),
+                    Frame::String(Bytes::from_static(b"type")),
+                    Frame::String(Bytes::from_static(type_name.as_bytes())),
+                    Frame::String(Bytes::from_static(b"bytes")),
+                    Frame::Integer(size as i64),
+                ])
+            })
+            .collect();
+        Ok(Frame::Array(keys))
+    }
+
+    pub fn pubsub(&self) -> &PubSub {
+        &self.pubsub
+    }
+
+    // reports the requested sections of the server stats
+    fn exec_info(&self, cmd: &Info) -> Result<Frame, ExecuteCommandError> {
+        let sections = match cmd.section() {
+            Some(section) => vec![section],
+            None => InfoSection::ALL.to_vec(),
+        };
+
+        let mut info = Vec::with_capacity(sections.len() * 2);
+        for section in sections {
+            let fields = match section {
+                InfoSection::Server => vec![field("uptime_secs", self.stats.uptime_secs())],
+                InfoSection::Clients => {
+                    vec![field("connected_clients", self.clients.len() as u64)]
+                }
+                InfoSection::Stats => vec![
+                    field("total_commands_processed", self.stats.commands_processed()),
+                    field("ops_per_sec", self.stats.ops_per_sec()),
+                ],
+                InfoSection::Memory => vec![
+                    field("used_memory", self.stats.used_memory()),
+                    field("max_memory", self.stats.max_memory()),
+                ],
+                InfoSection::Keyspaces => self
+                    .keyspace_snapshots()
+                    .into_iter()
+                    .map(|(name, snapshot)| {
+                        let fields = [
+                            field("keys", snapshot.keys),
+                            field("expiring_keys", snapshot.expiring_keys),
+                            field("used_memory", snapshot.used_memory),
+                            field("max_memory", snapshot.max_memory),
+                            field("hits", snapshot.hits),
+                            field("misses", snapshot.misses),
+                            field("expired", snapshot.expired),
+                            field("evicted", snapshot.evicted),
+                        ];
+                        [
+                            Frame::String(name),
+                            Frame::Map(fields.into_iter().flatten().collect()),
+                        ]
+                    })
+                    .collect(),
+            };
+            info.push(Frame::String(Bytes::from_static(
+                section.as_str().as_bytes(),
+            )));
+            info.push(Frame::Map(fields.into_iter().flatten().collect()));
+        }
+        Ok(Frame::Map(info))
+    }
+
+    /// Returns a snapshot of every keyspace ordered by name, keyspaces are locked one
+    /// at a time to count their keys.
+    pub fn keyspace_snapshots(&self) -> Vec<(Bytes, KeyspaceSnapshot)> {
+        let handle = self.keyspaces.read();
+        let mut snapshots: Vec<(Bytes, KeyspaceSnapshot)> = handle
+            .iter()
+            .map(|(name, keyspace)| (name.clone(), keyspace.snapshot()))
+            .collect();
+        snapshots.sort_by(|(a, _), (b, _)| a.cmp(b));
+        snapshots
+    }
+
+    pub fn monitors(&self) -> &Monitors {
+        &self.monitors
+    }
+
+    pub fn slowlog(&self) -> &SlowLog {
+        &self.slowlog
+    }
+
+    pub fn stats(&self) -> &ServerStats {
+        &self.stats
+    }
+
+    pub fn clients(&self) -> &Clients {
+        &self.clients
+    }
+
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+
+    pub fn watch_events(
+        &self,
+        keyspace: &Bytes,
+        pattern: Option<Bytes>,
+        subscriber: &Subscriber,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = self.keyspaces.read();
+        match handle.get(keyspace) {
+            Some(ks) => {
+                ks.events.watch(subscriber, pattern);
+                Ok(Frame::Boolean(true))
+            }
+            None => Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                str::from_utf8(&keyspace[..])?.to_string(),
+            )),
+        }
+    }
+
+    pub fn unwatch_events(&self, keyspace: &Bytes, subscriber: &Subscriber) -> bool {
+        let handle = self.keyspaces.read();
+        match handle.get(keyspace) {
+            Some(ks) => ks.events.unwatch(subscriber),
+            None => false,
         }
     }
 
@@ -114,10 +536,13 @@ impl Db {
             }
         }
 
-        let ks = Keyspace::new(
+        let mut ks = Keyspace::new(
+            cmd.keyspace(),
+            self.tracker.clone(),
             self.done.resubscribe(),
             self.wg.clone(),
             cmd.evictor(),
+            KeyspaceOptions::new(cmd.options()),
             self.evict.resubscribe(),
         );
 
@@ -129,6 +554,115 @@ impl Db {
         Ok(Frame::Boolean(true))
     }
 
+    fn exec_alter(&self, cmd: &Alter) -> Result<Frame, ExecuteCommandError> {
+        let mut handle = self.keyspaces.write();
+        match handle.get_mut(&cmd.keyspace()) {
+            Some(keyspace) => {
+                for option in cmd.options() {
+                    keyspace.options.write().apply(option);
+                }
+                if let Some(evictor) = cmd.evictor() {
+                    keyspace.set_evictor(evictor);
+                }
+                // a lowered quota applies right away instead of on the next write
+                keyspace.lock().evict_over_quota(None);
+                Ok(Frame::Boolean(true))
+            }
+            None => Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                str::from_utf8(&cmd.keyspace()[..])?.to_string(),
+            )),
+        }
+    }
+
+    // renames the keyspace in place, a keyspace that already has the new name is
+    // replaced so that a keyspace filled in the background can be swapped in atomically
+    fn exec_rename_keyspace(&self, cmd: &RenameKeyspace) -> Result<Frame, ExecuteCommandError> {
+        let mut handle = self.keyspaces.write();
+        let keyspace = match handle.remove(&cmd.source()) {
+            Some(keyspace) => keyspace,
+            None => {
+                return Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                    str::from_utf8(&cmd.source()[..])?.to_string(),
+                ))
+            }
+        };
+
+        keyspace.events.rename(cmd.destination());
+        {
+            let store = keyspace.store.lock();
+            self.invalidate_keys(&cmd.source(), &store);
+            self.invalidate_keys(&cmd.destination(), &store);
+        }
+        if let Some(replaced) = handle.insert(cmd.destination(), keyspace) {
+            self.invalidate_keys(&cmd.destination(), &replaced.store.lock());
+        }
+        Ok(Frame::Boolean(true))
+    }
+
+    // copies the keys, expiries, evictor and options of the keyspace into a new one,
+    // only the source keyspace is locked while its keys are copied
+    fn exec_copy_keyspace(&self, cmd: &CopyKeyspace) -> Result<Frame, ExecuteCommandError> {
+        let (store, expiring, evictor, options) = {
+            let handle = self.keyspaces.read();
+            if handle.contains_key(&cmd.destination()) {
+                return Err(ExecuteCommandError::KeyspaceExists(
+                    str::from_utf8(&cmd.destination()[..])?.to_string(),
+                ));
+            }
+            match handle.get(&cmd.source()) {
+                Some(keyspace) => (
+                    keyspace.store.clone(),
+                    keyspace.expiring.clone(),
+                    keyspace.evictor(),
+                    keyspace.options(),
+                ),
+                None => {
+                    return Err(ExecuteCommandError::KeyspaceDoesNotExist(
+                        str::from_utf8(&cmd.source()[..])?.to_string(),
+                    ))
+                }
+            }
+        };
+
+        let mut ks = Keyspace::new(
+            cmd.destination(),
+            self.tracker.clone(),
+            self.done.resubscribe(),
+            self.wg.clone(),
+            evictor,
+            options,
+            self.evict.resubscribe(),
+        );
+        {
+            // the store is always locked before the expiring keys
+            let store = store.lock();
+            let expiring = expiring.lock();
+            let mut copy = ks.store.lock();
+            for (key, value) in store.iter() {
+                copy.insert(key.clone(), value.clone());
+            }
+            *ks.expiring.lock() = expiring.clone();
+        }
+
+        let mut handle = self.keyspaces.write();
+        if handle.contains_key(&cmd.destination()) {
+            return Err(ExecuteCommandError::KeyspaceExists(
+                str::from_utf8(&cmd.destination()[..])?.to_string(),
+            ));
+        }
+        ks.start_expiring_evictor();
+        ks.start_max_memory_evictor();
+        handle.insert(cmd.destination(), ks);
+        Ok(Frame::Boolean(true))
+    }
+
+    // tells the clients caching keys of the keyspace that they have changed
+    fn invalidate_keys(&self, keyspace: &Bytes, store: &Store) {
+        for key in store.keys() {
+            self.tracker.invalidate(keyspace, key);
+        }
+    }
+
     fn exec_drop(&self, cmd: &Drop) -> Result<Frame, ExecuteCommandError> {
         let mut handle = self.keyspaces.write();
         if !handle.contains_key(&cmd.keyspace()) {
@@ -140,22 +674,37 @@ impl Db {
                 ));
             }
         }
-        handle.remove(&cmd.keyspace());
+        if let Some(removed) = handle.remove(&cmd.keyspace()) {
+            self.invalidate_keys(&cmd.keyspace(), &removed.store.lock());
+        }
         Ok(Frame::Boolean(true))
     }
 
     fn exec_keyspaces(&self) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
+        self.list_keyspaces(&self.keyspaces.read())
+    }
+
+    fn list_keyspaces(
+        &self,
+        handle: &HashMap<Bytes, Keyspace>,
+    ) -> Result<Frame, ExecuteCommandError> {
         let mut keyspaces = Vec::with_capacity(handle.keys().count());
         for key in handle.keys() {
             if let Some(keyspace) = handle.get(key) {
-                let mut map = Vec::with_capacity(2);
+                let mut map = Vec::with_capacity(14);
                 let name = Frame::String(key.clone());
                 let evictor = Frame::String(Bytes::copy_from_slice(keyspace.evictor().as_bytes()));
+                let options = keyspace.options();
                 map.push(Frame::String(Bytes::from_static(b"name")));
                 map.push(name);
                 map.push(Frame::String(Bytes::from_static(b"evictor")));
                 map.push(evictor);
+                map.extend(field("max_keys", options.max_keys));
+                map.extend(field("max_memory", options.max_memory));
+                map.extend(field("default_expire_after", options.default_expire_after));
+                map.extend(field("max_value_size", options.max_value_size));
+                map.push(Frame::String(Bytes::from_static(b"readonly")));
+                map.push(Frame::Boolean(options.read_only));
                 keyspaces.push(Frame::Map(map))
             } else {
                 continue;
@@ -163,190 +712,44 @@ impl Db {
         }
         Ok(Frame::Array(keyspaces))
     }
-
-    fn exec_set(&self, cmd: &Set) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
-        let keyspace = handle.get(&cmd.keyspace());
-        if let Some(ks) = keyspace {
-            if cmd.if_exists() || cmd.if_not_exists() {
-                if cmd.if_exists() {
-                    return ks.set_if_exists(cmd.key(), cmd.value(), cmd.expire_at());
-                } else {
-                    return ks.set_if_not_exists(cmd.key(), cmd.value(), cmd.expire_at());
-                }
-            } else {
-                return ks.set(cmd.key(), cmd.value(), cmd.expire_at());
-            }
-        }
-
-        Err(ExecuteCommandError::KeyspaceDoesNotExist(
-            str::from_utf8(&cmd.keyspace()[..])?.to_string(),
-        ))
-    }
-
-    fn exec_get(&self, cmd: &Get) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
-        let keyspace = handle.get(&cmd.keyspace());
-        if let Some(ks) = keyspace {
-            return ks.get(cmd.key());
-        }
-
-        Err(ExecuteCommandError::KeyspaceDoesNotExist(
-            str::from_utf8(&cmd.keyspace()[..])?.to_string(),
-        ))
-    }
-
-    fn exec_del(&self, cmd: &Del) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
-        let keyspace = handle.get(&cmd.keyspace());
-        if let Some(ks) = keyspace {
-            return ks.del(cmd.key());
-        }
-
-        Err(ExecuteCommandError::KeyspaceDoesNotExist(
-            str::from_utf8(&cmd.keyspace()[..])?.to_string(),
-        ))
-    }
-
-    fn exec_count(&self, cmd: &Count) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
-        let keyspace = handle.get(&cmd.keyspace());
-        if let Some(ks) = keyspace {
-            return ks.count();
-        }
-
-        Err(ExecuteCommandError::KeyspaceDoesNotExist(
-            str::from_utf8(&cmd.keyspace()[..])?.to_string(),
-        ))
-    }
-
-    fn exec_ttl(&self, cmd: &Ttl) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.keyspaces.read();
-        let keyspace = handle.get(&cmd.keyspace());
-        if let Some(ks) = keyspace {
-            return ks.ttl(cmd.key());
-        }
-
-        Err(ExecuteCommandError::KeyspaceDoesNotExist(
-            str::from_utf8(&cmd.keyspace()[..])?.to_string(),
-        ))
-    }
 }
 
 impl Keyspace {
     pub fn new(
+        name: Bytes,
+        tracker: Arc<Tracker>,
         done: broadcast::Receiver<()>,
         wg: WaitGroup,
         evictor: Evictor,
+        options: KeyspaceOptions,
         evict: broadcast::Receiver<()>,
     ) -> Self {
         let (drop_tx, _) = broadcast::channel(1);
+        let store = Store::new();
         Keyspace {
-            store: Arc::new(Mutex::new(HashMap::new())),
+            events: Arc::new(KeyspaceEvents::new(name, tracker)),
+            stats: Arc::new(KeyspaceStats::new()),
+            hotkeys: Mutex::new(HotKeys::new()),
+            expiring_keys: store.expiring(),
+            store: Arc::new(Mutex::new(store)),
             expiring: Arc::new(Mutex::new(HashMap::new())),
             evictor,
+            options: Arc::new(RwLock::new(options)),
             done,
             wg,
             drop: drop_tx,
+            stop_max_memory_evictor: None,
             evict,
         }
     }
-    pub fn set_if_not_exists(
-        &self,
-        key: Bytes,
-        value: Bytes,
-        expire_at: Option<u64>,
-    ) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.store.lock();
-        let val = handle.get(&key);
-        if val.is_some() {
-            return Ok(Frame::Boolean(false));
-        }
-        drop(handle);
-        self.set(key, value, expire_at)
-    }
-
-    pub fn set_if_exists(
-        &self,
-        key: Bytes,
-        value: Bytes,
-        expire_at: Option<u64>,
-    ) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.store.lock();
-        let val = handle.get(&key);
-        if val.is_none() {
-            return Ok(Frame::Boolean(false));
-        }
-        drop(handle);
-        self.set(key, value, expire_at)
-    }
-
-    pub fn set(
-        &self,
-        key: Bytes,
-        value: Bytes,
-        expire_at: Option<u64>,
-    ) -> Result<Frame, ExecuteCommandError> {
-        let mut handle = self.store.lock();
-        let value = Value::new(value, expire_at);
-        handle.insert(key.clone(), value);
-        if let Some(expiry) = expire_at {
-            let mut expring_handle = self.expiring.lock();
-            expring_handle.insert(key, expiry);
-        }
-        Ok(Frame::Boolean(true))
-    }
-
-    pub fn get(&self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
-        let mut handle = self.store.lock();
-        if let Some(val) = handle.get_mut(&key) {
-            val.touch();
-            if let Some(expiry) = val.expire_at() {
-                let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-                if expiry < current_time {
-                    handle.remove(&key);
-                    return Ok(Frame::Null);
-                }
-            }
-            return Ok(Frame::String(val.data()));
-        }
-        Ok(Frame::Null)
-    }
-
-    pub fn del(&self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
-        let mut handle = self.store.lock();
-        let result = handle.remove(&key);
-        Ok(Frame::Boolean(result.is_some()))
-    }
-
-    pub fn count(&self) -> Result<Frame, ExecuteCommandError> {
-        let handle = self.store.lock();
-        let count = handle.iter().count();
-        Ok(Frame::Integer(count as i64))
-    }
 
-    pub fn ttl(&self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
-        let mut handle = self.store.lock();
-        if let Some(val) = handle.get_mut(&key) {
-            val.touch();
-            if let Some(expiry) = val.expire_at() {
-                let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-                if expiry <= current_time {
-                    handle.remove(&key);
-                    return Ok(Frame::Null);
-                } else {
-                    return Ok(Frame::Integer(((expiry - current_time) * 1000) as i64));
-                }
-            }
-            return Ok(Frame::Null);
-        }
-        Ok(Frame::Null)
-    }
     fn start_expiring_evictor(&self) {
         let mut done = self.done.resubscribe();
         let wg = self.wg.clone();
         let expiring = self.expiring.clone();
         let store = self.store.clone();
+        let events = self.events.clone();
+        let stats = self.stats.clone();
         let mut drop_rx = self.drop.subscribe();
         tokio::spawn(async move {
             debug!("expiring evictor started");
@@ -363,8 +766,10 @@ impl Keyspace {
                         break;
                     }
                     _ = time::sleep(Duration::from_millis(500)) => {
-                        let mut expring_handle = expiring.lock();
+                        // the store is always locked before the expiring keys, in the same
+                        // order as writers, otherwise the evictor can deadlock with them
                         let mut store_handle = store.lock();
+                        let mut expring_handle = expiring.lock();
                         let mut expired_keys = Vec::with_capacity(5);
 
                         for (idx, (key, expiry)) in expring_handle.iter().enumerate() {
@@ -381,7 +786,7 @@ impl Keyspace {
                             };
                             if *expiry <= current_time {
                                 expired_keys.push(key.clone());
-                                store_handle.remove(key);
+                                expire(&mut store_handle, key, &stats, &events);
                             }
                         }
 
@@ -394,15 +799,20 @@ impl Keyspace {
         });
     }
 
-    fn start_max_memory_evictor(&self) {
+    fn start_max_memory_evictor(&mut self) {
         if self.evictor == Evictor::Nop {
             return;
         }
+        let (stop_tx, mut stop_rx) = broadcast::channel::<()>(1);
+        self.stop_max_memory_evictor = Some(stop_tx);
         let mut done = self.done.resubscribe();
         let mut drop_rx = self.drop.subscribe();
         let mut evict_rx = self.evict.resubscribe();
         let wg = self.wg.clone();
         let store = self.store.clone();
+        let events = self.events.clone();
+        let stats = self.stats.clone();
+        let options = self.options.clone();
         let evictor = self.evictor;
         tokio::spawn(async move {
             debug!("max memory evictor started");
@@ -418,46 +828,23 @@ impl Keyspace {
                         debug!("shutting down max memory evictor, keyspace is dropped");
                         break;
                     }
+                    _ = stop_rx.recv() => {
+                        drop(wg);
+                        debug!("shutting down max memory evictor, evictor is replaced");
+                        break;
+                    }
                     _ = evict_rx.recv() => {
-                        match evictor {
-                            Evictor::Lru => {
-                                let mut handle = store.lock();
-                                let mut lru = Instant::now();
-                                let mut to_evict: Option<Bytes> = None;
-                                for (idx, (key, value)) in handle.iter().enumerate() {
-                                    if idx >= MAX_MEMORY_EVICTOR_SAMPLE_SIZE as usize {
-                                        break;
-                                    }
-
-                                    let last_accessed = value.last_accessed();
-
-                                    if last_accessed < lru {
-                                        lru = last_accessed;
-                                        to_evict = Some(key.clone());
-                                    }
-                                }
-
-                                if let Some(key) = to_evict {
-                                    debug!("key '{:?}' evicted using lru policy", key);
-                                    handle.remove(&key);
-                                }
-                            },
-                            Evictor::Random => {
-                                let mut handle = store.lock();
-                                let mut to_evict: Option<Bytes> = None;
-                                for (idx, key) in handle.keys().enumerate() {
-                                    if idx >= MAX_MEMORY_EVICTOR_SAMPLE_SIZE as usize {
-                                        break;
-                                    }
-                                    to_evict = Some(key.clone());
-                                }
-
-                                if let Some(key) = to_evict {
-                                    debug!("key '{:?}' evicted using random policy", key);
-                                    handle.remove(&key);
-                                }
-                            },
-                            _ => unreachable!(),
+                        // keyspaces with a quota are only evicted when they exceed it
+                        if options.read().max_memory > 0 {
+                            continue;
+                        }
+                        let mut handle = store.lock();
+                        if let Some(key) = pick_victim(&handle, evictor, None) {
+                            debug!("key '{:?}' evicted using {:?} policy", key, evictor);
+                            handle.remove(&key);
+                            stats.record_evicted();
+                            events.emit(KeyspaceEvent::Evicted, &key);
+                            events.invalidate(&key);
                         }
                     }
                 }
@@ -465,26 +852,1127 @@ impl Keyspace {
         });
     }
 
-    pub fn evictor(&self) -> Evictor {
-        self.evictor
-    }
-}
+    fn snapshot(&self) -> KeyspaceSnapshot {
+        let (keys, used_memory) = {
+            let store = self.store.lock();
+            (store.len(), store.memory_usage())
+        };
 
-impl Value {
-    pub fn new(data: Bytes, expire_at: Option<u64>) -> Self {
-        Value {
-            data,
-            last_accessed: Instant::now(),
-            expire_at,
+        KeyspaceSnapshot {
+            evictor: self.evictor,
+            keys: keys as u64,
+            expiring_keys: self.expiring_keys.load(Ordering::Relaxed),
+            used_memory: used_memory as u64,
+            max_memory: self.options().max_memory,
+            hits: self.stats.hits(),
+            misses: self.stats.misses(),
+            expired: self.stats.expired(),
+            evicted: self.stats.evicted(),
         }
     }
 
-    pub fn touch(&mut self) {
+    pub fn evictor(&self) -> Evictor {
+        self.evictor
+    }
+
+    // swaps the evictor in place, the running max memory evictor is stopped and one is
+    // started for the new evictor while the keys are left untouched
+    fn set_evictor(&mut self, evictor: Evictor) {
+        if self.evictor == evictor {
+            return;
+        }
+        self.stop_max_memory_evictor.take();
+        self.evictor = evictor;
+        self.start_max_memory_evictor();
+    }
+
+    pub fn options(&self) -> KeyspaceOptions {
+        *self.options.read()
+    }
+
+    /// Locks the keyspace store, commands are executed against the returned handle.
+    pub fn lock(&self) -> KeyspaceHandle<'_> {
+        KeyspaceHandle {
+            keyspace: self,
+            store: self.store.lock(),
+        }
+    }
+}
+
+impl KeyspaceHandle<'_> {
+    /// Executes a command scoped to this keyspace, commands that are not
+    /// scoped to a keyspace are handled by the db.
+    pub fn execute(&mut self, command: &Command) -> Result<Frame, ExecuteCommandError> {
+        let modified = modified_key(command);
+        if let Some(key) = &modified {
+            self.check_write(command, key)?;
+        }
+        // writes check the max value size before changing the value, except for writes
+        // into a json document whose new size is only known once applied. The document
+        // is kept to undo them, it is no larger than the limit unless it was written
+        // before the limit was set
+        let previous = match (command, &modified) {
+            (Command::JsonSet(cmd), Some(key)) if !cmd.path().is_root() => Some(key),
+            (Command::JsonIncrBy(_), Some(key)) => Some(key),
+            _ => None,
+        }
+        .filter(|_| self.keyspace.options().max_value_size > 0)
+        .map(|key| self.store.get(key).cloned());
+        // every write gives the key a new version, comparing versions tells whether the
+        // command changed the key
+        let version = modified.as_ref().and_then(|key| self.version(key));
+
+        let result = match command {
+            Command::Set(cmd) => match (cmd.if_version(), cmd.if_value_equals()) {
+                (Some(version), _) => {
+                    self.set_if_version(cmd.key(), cmd.value(), cmd.expire_at()?, version)
+                }
+                (_, Some(expected)) => {
+                    self.set_if_value_equals(cmd.key(), cmd.value(), cmd.expire_at()?, expected)
+                }
+                _ if cmd.if_exists() => {
+                    self.set_if_exists(cmd.key(), cmd.value(), cmd.expire_at()?)
+                }
+                _ if cmd.if_not_exists() => {
+                    self.set_if_not_exists(cmd.key(), cmd.value(), cmd.expire_at()?)
+                }
+                _ => self.set(cmd.key(), cmd.value(), cmd.expire_at()?),
+            },
+            Command::Get(cmd) => self.get(cmd.key(), cmd.with_version()),
+            Command::Del(cmd) => match cmd.if_value_equals() {
+                Some(expected) => self.del_if_value_equals(cmd.key(), expected),
+                None => self.del(cmd.key()),
+            },
+            Command::Count(_) => self.count(),
+            Command::Ttl(cmd) => self.ttl(cmd.key()),
+            Command::JsonSet(cmd) => self.json_set(cmd.key(), cmd.path(), cmd.value()),
+            Command::JsonGet(cmd) => self.json_get(cmd.key(), cmd.path(), cmd.native()),
+            Command::JsonDel(cmd) => self.json_del(cmd.key(), cmd.path()),
+            Command::JsonIncrBy(cmd) => self.json_incr_by(cmd.key(), cmd.path(), cmd.by()),
+            Command::PfAdd(cmd) => self.pf_add(cmd.key(), cmd.elements()),
+            Command::PfCount(cmd) => self.pf_count(cmd.keys()),
+            Command::PfMerge(cmd) => self.pf_merge(cmd.destination(), cmd.sources()),
+            Command::BfReserve(cmd) => self.bf_reserve(cmd.key(), cmd.capacity(), cmd.error_rate()),
+            Command::BfAdd(cmd) => self.bf_add(cmd.key(), cmd.items(), false),
+            Command::BfMAdd(cmd) => self.bf_add(cmd.key(), cmd.items(), true),
+            Command::BfExists(cmd) => self.bf_exists(cmd.key(), cmd.items(), false),
+            Command::BfMExists(cmd) => self.bf_exists(cmd.key(), cmd.items(), true),
+            Command::SetBit(cmd) => self.setbit(cmd.key(), cmd.offset(), cmd.value()),
+            Command::GetBit(cmd) => self.getbit(cmd.key(), cmd.offset()),
+            Command::BitCount(cmd) => self.bitcount(cmd.key(), cmd.range()),
+            Command::BitPos(cmd) => self.bitpos(cmd.key(), cmd.bit(), cmd.start(), cmd.end()),
+            Command::BitOp(cmd) => self.bitop(cmd.operation(), cmd.destination(), cmd.keys()),
+            Command::Append(cmd) => self.append(cmd.key(), cmd.value()),
+            Command::GetRange(cmd) => self.getrange(cmd.key(), cmd.start(), cmd.end()),
+            Command::SetRange(cmd) => self.setrange(cmd.key(), cmd.offset(), cmd.value()),
+            Command::StrLen(cmd) => self.strlen(cmd.key()),
+            Command::MGet(cmd) => self.mget(cmd.keys()),
+            Command::MemoryUsage(cmd) => self.memory_usage(cmd.key()),
+            Command::HotKeys(cmd) => Ok(self.hotkeys(cmd.limit())),
+            Command::Truncate(_) => self.truncate(),
+            Command::Create(_)
+            | Command::Alter(_)
+            | Command::RenameKeyspace(_)
+            | Command::CopyKeyspace(_)
+            | Command::Drop(_)
+            | Command::Keyspaces
+            | Command::Ping
+            | Command::Multi
+            | Command::Exec
+            | Command::Discard
+            | Command::Subscribe(_)
+            | Command::PSubscribe(_)
+            | Command::Unsubscribe(_)
+            | Command::PUnsubscribe(_)
+            | Command::Publish(_)
+            | Command::WatchEvents(_)
+            | Command::UnwatchEvents(_)
+            | Command::Tracking(_)
+            | Command::Auth(_)
+            | Command::ClientList
+            | Command::ClientInfo
+            | Command::ClientKill(_)
+            | Command::ClientSetName(_)
+            | Command::ClientPause(_)
+            | Command::Info(_)
+            | Command::SlowLogGet(_)
+            | Command::SlowLogLen
+            | Command::SlowLogReset
+            | Command::Monitor
+            | Command::MemoryStats
+            | Command::BigKeys(_) => unreachable!("command is not scoped to a keyspace"),
+        };
+
+        if result.is_ok() {
+            if let Some(key) = modified {
+                if let Some(previous) = previous {
+                    self.check_value_size(&key, previous)?;
+                }
+                match self.version(&key) {
+                    Some(current) if Some(current) != version => {
+                        self.keyspace.events.emit(KeyspaceEvent::Set, &key)
+                    }
+                    None if version.is_some() => {
+                        self.keyspace.events.emit(KeyspaceEvent::Del, &key)
+                    }
+                    _ => {}
+                }
+                self.keyspace.events.invalidate(&key);
+                self.evict_over_quota(Some(&key));
+            }
+        }
+        result
+    }
+
+    fn version(&self, key: &Bytes) -> Option<u64> {
+        self.store.get(key).map(Value::version)
+    }
+
+    // puts the previous json document back if the write made it larger than the max
+    // value size of the keyspace
+    fn check_value_size(
+        &mut self,
+        key: &Bytes,
+        previous: Option<Value>,
+    ) -> Result<(), ExecuteCommandError> {
+        let size = match self.store.get(key) {
+            Some(val) => val.data().size(),
+            None => return Ok(()),
+        };
+        if let Err(e) = self.keyspace.options().check_value_size(size) {
+            match previous {
+                Some(previous) => self.store.insert(key.clone(), previous),
+                None => self.store.remove(key),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // evicts keys until the keyspace is back under its memory quota, this is independent
+    // of the server wide max memory so that a keyspace can not push out the keys of others.
+    // The key just written is never evicted as the write would be lost right away.
+    fn evict_over_quota(&mut self, written: Option<&Bytes>) {
+        let max_memory = self.keyspace.options().max_memory;
+        if max_memory == 0 {
+            return;
+        }
+        while self.store.memory_usage() as u64 > max_memory {
+            let Some(key) = pick_victim(&self.store, self.keyspace.evictor, written) else {
+                break;
+            };
+            debug!("key '{:?}' evicted over the keyspace quota", key);
+            self.store.remove(&key);
+            self.keyspace.stats.record_evicted();
+            self.keyspace.events.emit(KeyspaceEvent::Evicted, &key);
+            self.keyspace.events.invalidate(&key);
+        }
+    }
+
+    // rejects writes to a read only keyspace and writes that could grow the keyspace
+    // past its limits, deletes are allowed so that space can always be freed
+    fn check_write(&self, command: &Command, key: &Bytes) -> Result<(), ExecuteCommandError> {
+        let options = self.keyspace.options();
+        if options.read_only {
+            return Err(ExecuteCommandError::ReadOnly);
+        }
+        if matches!(command, Command::Del(_) | Command::JsonDel(_)) {
+            return Ok(());
+        }
+        if options.max_keys > 0
+            && self.store.len() as u64 >= options.max_keys
+            && !self.store.contains_key(key)
+        {
+            return Err(ExecuteCommandError::MaxKeysReached(options.max_keys));
+        }
+        // keyspaces with an evictor make room for the write once it is done instead
+        if options.max_memory > 0
+            && self.keyspace.evictor == Evictor::Nop
+            && self.store.memory_usage() as u64 >= options.max_memory
+        {
+            return Err(ExecuteCommandError::MaxMemoryReached(options.max_memory));
+        }
+        Ok(())
+    }
+
+    /// Removes every key of the keyspace while keeping its evictor and options, returns
+    /// the number of keys removed.
+    pub fn truncate(&mut self) -> Result<Frame, ExecuteCommandError> {
+        if self.keyspace.options().read_only {
+            return Err(ExecuteCommandError::ReadOnly);
+        }
+        let removed: Vec<Bytes> = self.store.drain().map(|(key, _)| key).collect();
+        self.keyspace.expiring.lock().clear();
+        for key in &removed {
+            self.keyspace.events.emit(KeyspaceEvent::Del, key);
+            self.keyspace.events.invalidate(key);
+        }
+        Ok(Frame::Integer(removed.len() as i64))
+    }
+
+    pub fn memory_usage(&mut self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
+        match live_value(&mut self.store, &key, self.keyspace)? {
+            Some(val) => Ok(Frame::Integer(entry_size(&key, &val) as i64)),
+            None => Ok(Frame::Null),
+        }
+    }
+
+    pub fn hotkeys(&mut self, limit: usize) -> Frame {
+        let top = self.keyspace.hotkeys.lock().top(limit);
+        Frame::Array(
+            top.into_iter()
+                .map(|(key, count)| {
+                    Frame::Map(vec![
+                        Frame::String(Bytes::from_static(b"key")),
+                        Frame::String(key),
+                        Frame::String(Bytes::from_static(b"count")),
+                        Frame::Integer(count as i64),
+                    ])
+                })
+                .collect(),
+        )
+    }
+
+    pub fn mget(&mut self, keys: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.get(key.clone(), false) {
+                Ok(frame) => values.push(frame),
+                // values that are not strings are returned as null like missing keys
+                Err(ExecuteCommandError::WrongType) => values.push(Frame::Null),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Frame::Array(values))
+    }
+
+    pub fn set_if_not_exists(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expire_at: Option<u64>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        if self.store.contains_key(&key) {
+            return Ok(Frame::Boolean(false));
+        }
+        self.set(key, value, expire_at)
+    }
+
+    pub fn set_if_exists(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expire_at: Option<u64>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        if !self.store.contains_key(&key) {
+            return Ok(Frame::Boolean(false));
+        }
+        self.set(key, value, expire_at)
+    }
+
+    /// Sets the value only if the current version of the key matches, the version
+    /// of a key that does not exist is 0.
+    pub fn set_if_version(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expire_at: Option<u64>,
+        version: u64,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let current = live_value(&mut self.store, &key, self.keyspace)?
+            .map(|val| val.version())
+            .unwrap_or(0);
+        if current != version {
+            return Err(ExecuteCommandError::VersionConflict(current));
+        }
+        self.set(key, value, expire_at)
+    }
+
+    /// Sets the value only if the key holds exactly the expected value, this lets
+    /// a client replace a value it has read without overwriting concurrent writes.
+    pub fn set_if_value_equals(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expire_at: Option<u64>,
+        expected: Bytes,
+    ) -> Result<Frame, ExecuteCommandError> {
+        if !self.value_equals(&key, &expected)? {
+            return Ok(Frame::Boolean(false));
+        }
+        self.set(key, value, expire_at)
+    }
+
+    pub fn set(
+        &mut self,
+        key: Bytes,
+        value: Bytes,
+        expire_at: Option<u64>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        options.check_value_size(value.len())?;
+        let expire_at = match expire_at {
+            None if options.default_expire_after > 0 => Some(
+                SystemTime::now()
+                    .add(Duration::from_millis(options.default_expire_after))
+                    .duration_since(UNIX_EPOCH)?
+                    .as_secs(),
+            ),
+            expire_at => expire_at,
+        };
+
+        self.keyspace.hotkeys.lock().record(&key);
+        let handle = &mut *self.store;
+        let value = Value::new(Data::String(value), expire_at);
+        handle.insert(key.clone(), value);
+        if let Some(expiry) = expire_at {
+            let mut expring_handle = self.keyspace.expiring.lock();
+            expring_handle.insert(key, expiry);
+        }
+        Ok(Frame::Boolean(true))
+    }
+
+    pub fn get(&mut self, key: Bytes, with_version: bool) -> Result<Frame, ExecuteCommandError> {
+        self.keyspace.hotkeys.lock().record(&key);
+        let handle = &mut *self.store;
+        let stats = &self.keyspace.stats;
+        let expired = match handle.get(&key).and_then(|val| val.expire_at()) {
+            Some(expiry) => expiry < SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            None => false,
+        };
+        if expired {
+            expire(handle, &key, stats, &self.keyspace.events);
+            stats.record_miss();
+            return Ok(Frame::Null);
+        }
+        if let Some(mut val) = handle.get_mut(&key) {
+            val.touch();
+            stats.record_hit();
+            let data = match val.data() {
+                Data::String(data) => data.clone(),
+                Data::HyperLogLog(hll) => hll.to_bytes(),
+                Data::Bloom(filter) => filter.to_bytes(),
+                Data::Json(_) => return Err(ExecuteCommandError::WrongType),
+            };
+            if with_version {
+                return Ok(Frame::Map(vec![
+                    Frame::String(Bytes::from_static(b"value")),
+                    Frame::String(data),
+                    Frame::String(Bytes::from_static(b"version")),
+                    Frame::Integer(val.version() as i64),
+                ]));
+            }
+            return Ok(Frame::String(data));
+        }
+        stats.record_miss();
+        Ok(Frame::Null)
+    }
+
+    pub fn del(&mut self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let result = handle.remove(&key);
+        Ok(Frame::Boolean(result.is_some()))
+    }
+
+    /// Deletes the key only if it holds exactly the expected value, used to safely
+    /// release locks and leases that may have been taken over by another client.
+    pub fn del_if_value_equals(
+        &mut self,
+        key: Bytes,
+        expected: Bytes,
+    ) -> Result<Frame, ExecuteCommandError> {
+        if !self.value_equals(&key, &expected)? {
+            return Ok(Frame::Boolean(false));
+        }
+        self.del(key)
+    }
+
+    fn value_equals(&mut self, key: &Bytes, expected: &Bytes) -> Result<bool, ExecuteCommandError> {
+        match live_value(&mut self.store, key, self.keyspace)? {
+            Some(val) => match val.data() {
+                Data::String(data) => Ok(data == expected),
+                _ => Err(ExecuteCommandError::WrongType),
+            },
+            None => Ok(false),
+        }
+    }
+
+    pub fn count(&mut self) -> Result<Frame, ExecuteCommandError> {
+        let count = self.store.len();
+        Ok(Frame::Integer(count as i64))
+    }
+
+    pub fn ttl(&mut self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            if let Some(expiry) = val.expire_at() {
+                let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                return Ok(Frame::Integer(((expiry - current_time) * 1000) as i64));
+            }
+        }
+        Ok(Frame::Null)
+    }
+
+    pub fn json_set(
+        &mut self,
+        key: Bytes,
+        path: &Path,
+        value: serde_json::Value,
+    ) -> Result<Frame, ExecuteCommandError> {
+        if path.is_root() {
+            self.keyspace
+                .options()
+                .check_value_size(value.to_string().len())?;
+        }
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let updated = match val.data_mut() {
+                Data::Json(doc) => json::set(doc, path, value)?,
+                _ => return Err(ExecuteCommandError::WrongType),
+            };
+            val.bump_version();
+            return Ok(Frame::Boolean(updated));
+        }
+
+        if !path.is_root() {
+            return Err(JsonError::NotRoot.into());
+        }
+        handle.insert(key, Value::new(Data::Json(value), None));
+        Ok(Frame::Boolean(true))
+    }
+
+    pub fn json_get(
+        &mut self,
+        key: Bytes,
+        path: &Path,
+        native: bool,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let doc = match val.data() {
+                Data::Json(doc) => doc,
+                _ => return Err(ExecuteCommandError::WrongType),
+            };
+            return match json::get(doc, path) {
+                Some(value) if native => Ok(json::to_frame(value)),
+                Some(value) => Ok(Frame::String(Bytes::from(value.to_string()))),
+                None => Ok(Frame::Null),
+            };
+        }
+        Ok(Frame::Null)
+    }
+
+    pub fn json_del(&mut self, key: Bytes, path: &Path) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let deleted = match live_value(handle, &key, self.keyspace)? {
+            Some(mut val) => {
+                let deleted = match val.data_mut() {
+                    Data::Json(_) if path.is_root() => None,
+                    Data::Json(doc) => Some(json::del(doc, path)),
+                    _ => return Err(ExecuteCommandError::WrongType),
+                };
+                if deleted == Some(true) {
+                    val.bump_version();
+                }
+                deleted
+            }
+            None => return Ok(Frame::Boolean(false)),
+        };
+
+        match deleted {
+            Some(deleted) => Ok(Frame::Boolean(deleted)),
+            None => Ok(Frame::Boolean(handle.remove(&key).is_some())),
+        }
+    }
+
+    pub fn json_incr_by(
+        &mut self,
+        key: Bytes,
+        path: &Path,
+        by: &Number,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let doc = match val.data_mut() {
+                Data::Json(doc) => doc,
+                _ => return Err(ExecuteCommandError::WrongType),
+            };
+            return match json::incr_by(doc, path, by)? {
+                Some(number) => {
+                    val.bump_version();
+                    Ok(json::number_to_frame(&number))
+                }
+                None => Ok(Frame::Null),
+            };
+        }
+        Ok(Frame::Null)
+    }
+
+    pub fn pf_add(&mut self, key: Bytes, elements: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let hll = val.data_mut().as_hyperloglog_mut()?;
+            options.check_value_size(hll.heap_size())?;
+            let mut changed = false;
+            for element in elements {
+                changed |= hll.add(element);
+            }
+            if changed {
+                val.bump_version();
+            }
+            return Ok(Frame::Boolean(changed));
+        }
+
+        let mut hll = HyperLogLog::new();
+        options.check_value_size(hll.heap_size())?;
+        for element in elements {
+            hll.add(element);
+        }
+        handle.insert(key, Value::new(Data::HyperLogLog(hll), None));
+        Ok(Frame::Boolean(true))
+    }
+
+    pub fn pf_count(&mut self, keys: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let mut union: Option<HyperLogLog> = None;
+        for key in keys {
+            if let Some(mut val) = live_value(handle, key, self.keyspace)? {
+                val.touch();
+                let hll = val.data_mut().as_hyperloglog_mut()?;
+                match union.as_mut() {
+                    Some(union) => union.merge(hll),
+                    None if keys.len() == 1 => return Ok(Frame::Integer(hll.count() as i64)),
+                    None => union = Some(hll.clone()),
+                }
+            }
+        }
+
+        Ok(Frame::Integer(
+            union.map(|hll| hll.count() as i64).unwrap_or_default(),
+        ))
+    }
+
+    pub fn pf_merge(
+        &mut self,
+        destination: Bytes,
+        sources: &[Bytes],
+    ) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        let mut merged = HyperLogLog::new();
+        for key in sources {
+            if let Some(mut val) = live_value(handle, key, self.keyspace)? {
+                val.touch();
+                merged.merge(val.data_mut().as_hyperloglog_mut()?);
+            }
+        }
+
+        // merging never changes the size of the registers
+        if let Some(mut val) = live_value(handle, &destination, self.keyspace)? {
+            val.touch();
+            let hll = val.data_mut().as_hyperloglog_mut()?;
+            options.check_value_size(hll.heap_size())?;
+            hll.merge(&merged);
+            val.bump_version();
+            return Ok(Frame::Boolean(true));
+        }
+
+        options.check_value_size(merged.heap_size())?;
+        handle.insert(destination, Value::new(Data::HyperLogLog(merged), None));
+        Ok(Frame::Boolean(true))
+    }
+
+    pub fn bf_reserve(
+        &mut self,
+        key: Bytes,
+        capacity: u64,
+        error_rate: f64,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if live_value(handle, &key, self.keyspace)?.is_some() {
+            return Err(ExecuteCommandError::KeyExists);
+        }
+        let filter = BloomFilter::new(capacity, error_rate);
+        self.keyspace
+            .options()
+            .check_value_size(filter.heap_size())?;
+        handle.insert(key, Value::new(Data::Bloom(filter), None));
+        Ok(Frame::Boolean(true))
+    }
+
+    pub fn bf_add(
+        &mut self,
+        key: Bytes,
+        items: &[Bytes],
+        multi: bool,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        if live_value(handle, &key, self.keyspace)?.is_none() {
+            let filter = BloomFilter::new(bloom::DEFAULT_CAPACITY, bloom::DEFAULT_ERROR_RATE);
+            options.check_value_size(filter.heap_size())?;
+            handle.insert(key.clone(), Value::new(Data::Bloom(filter), None));
+        }
+
+        let mut val = handle
+            .get_mut(&key)
+            .expect("bloom filter was inserted above");
+        val.touch();
+        let filter = val.data_mut().as_bloom_mut()?;
+        // filters never grow past the size they were created with
+        options.check_value_size(filter.heap_size())?;
+        let mut results = Vec::with_capacity(items.len());
+        let mut added = false;
+        for item in items {
+            let new = filter.add(item);
+            added |= new;
+            results.push(Frame::Boolean(new));
+        }
+        if added {
+            val.bump_version();
+        }
+
+        if multi {
+            Ok(Frame::Array(results))
+        } else {
+            Ok(results.pop().unwrap_or(Frame::Boolean(false)))
+        }
+    }
+
+    pub fn bf_exists(
+        &mut self,
+        key: Bytes,
+        items: &[Bytes],
+        multi: bool,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let mut results = Vec::with_capacity(items.len());
+        match live_value(handle, &key, self.keyspace)? {
+            Some(mut val) => {
+                val.touch();
+                let filter = val.data_mut().as_bloom_mut()?;
+                for item in items {
+                    results.push(Frame::Boolean(filter.contains(item)));
+                }
+            }
+            None => results.resize_with(items.len(), || Frame::Boolean(false)),
+        }
+
+        if multi {
+            Ok(Frame::Array(results))
+        } else {
+            Ok(results.pop().unwrap_or(Frame::Boolean(false)))
+        }
+    }
+
+    pub fn setbit(
+        &mut self,
+        key: Bytes,
+        offset: u64,
+        value: bool,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        let len = (offset / 8 + 1) as usize;
+        if live_value(handle, &key, self.keyspace)?.is_none() {
+            options.check_value_size(len)?;
+            handle.insert(key.clone(), Value::new(Data::String(Bytes::new()), None));
+        }
+        let mut val = handle.get_mut(&key).expect("value was inserted above");
+        val.touch();
+        let data = val.data_mut().as_string_mut()?;
+        options.check_value_size(data.len().max(len))?;
+        let mut buf = take_mut(data);
+        let previous = bitmap::set_bit(&mut buf, offset, value);
+        *data = buf.freeze();
+        val.bump_version();
+        Ok(Frame::Integer(previous as i64))
+    }
+
+    pub fn getbit(&mut self, key: Bytes, offset: u64) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            return Ok(Frame::Integer(bitmap::get_bit(data, offset) as i64));
+        }
+        Ok(Frame::Integer(0))
+    }
+
+    pub fn bitcount(
+        &mut self,
+        key: Bytes,
+        range: Option<(i64, i64)>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            let (start, end) = range.unwrap_or((0, -1));
+            let count = match bitmap::resolve_range(data.len(), start, end) {
+                Some((start, end)) => bitmap::count(&data[start..=end]),
+                None => 0,
+            };
+            return Ok(Frame::Integer(count as i64));
+        }
+        Ok(Frame::Integer(0))
+    }
+
+    pub fn bitpos(
+        &mut self,
+        key: Bytes,
+        bit: bool,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let data = match live_value(handle, &key, self.keyspace)? {
+            Some(mut val) => {
+                val.touch();
+                val.data_mut().as_string_mut()?.clone()
+            }
+            None => Bytes::new(),
+        };
+
+        let open_ended = end.is_none();
+        let range = bitmap::resolve_range(data.len(), start.unwrap_or(0), end.unwrap_or(-1));
+        let (start, end) = match range {
+            Some(range) => range,
+            None if !bit && data.is_empty() => return Ok(Frame::Integer(0)),
+            None => return Ok(Frame::Integer(-1)),
+        };
+
+        match bitmap::position(&data[start..=end], bit, start) {
+            Some(position) => Ok(Frame::Integer(position as i64)),
+            // values are treated as if they were padded with zeroes to the right,
+            // unless the caller limited the search with an explicit end
+            None if !bit && open_ended => Ok(Frame::Integer((data.len() * 8) as i64)),
+            None => Ok(Frame::Integer(-1)),
+        }
+    }
+
+    pub fn bitop(
+        &mut self,
+        operation: BitOperation,
+        destination: Bytes,
+        keys: &[Bytes],
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        let mut sources = Vec::with_capacity(keys.len());
+        for key in keys {
+            match live_value(handle, key, self.keyspace)? {
+                Some(mut val) => {
+                    val.touch();
+                    sources.push(val.data_mut().as_string_mut()?.clone());
+                }
+                None => sources.push(Bytes::new()),
+            }
+        }
+
+        let sources: Vec<&[u8]> = sources.iter().map(|source| &source[..]).collect();
+        let result = bitmap::operate(operation, &sources).freeze();
+        let len = result.len();
+        if result.is_empty() {
+            handle.remove(&destination);
+        } else {
+            self.keyspace.options().check_value_size(len)?;
+            handle.insert(destination, Value::new(Data::String(result), None));
+        }
+        Ok(Frame::Integer(len as i64))
+    }
+
+    pub fn append(&mut self, key: Bytes, value: Bytes) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            options.check_value_size(data.len() + value.len())?;
+            let mut buf = take_mut(data);
+            buf.extend_from_slice(&value);
+            let len = buf.len();
+            *data = buf.freeze();
+            val.bump_version();
+            return Ok(Frame::Integer(len as i64));
+        }
+
+        let len = value.len();
+        options.check_value_size(len)?;
+        handle.insert(key, Value::new(Data::String(value), None));
+        Ok(Frame::Integer(len as i64))
+    }
+
+    pub fn getrange(
+        &mut self,
+        key: Bytes,
+        start: i64,
+        end: i64,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            return match bitmap::resolve_range(data.len(), start, end) {
+                Some((start, end)) => Ok(Frame::String(data.slice(start..=end))),
+                None => Ok(Frame::String(Bytes::new())),
+            };
+        }
+        Ok(Frame::String(Bytes::new()))
+    }
+
+    pub fn setrange(
+        &mut self,
+        key: Bytes,
+        offset: u64,
+        value: Bytes,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let options = self.keyspace.options();
+        let handle = &mut *self.store;
+        let offset = offset as usize;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            if value.is_empty() {
+                return Ok(Frame::Integer(data.len() as i64));
+            }
+            options.check_value_size(data.len().max(offset + value.len()))?;
+            let mut buf = take_mut(data);
+            if buf.len() < offset + value.len() {
+                buf.resize(offset + value.len(), 0);
+            }
+            buf[offset..offset + value.len()].copy_from_slice(&value);
+            let len = buf.len();
+            *data = buf.freeze();
+            val.bump_version();
+            return Ok(Frame::Integer(len as i64));
+        }
+
+        if value.is_empty() {
+            return Ok(Frame::Integer(0));
+        }
+        options.check_value_size(offset + value.len())?;
+        let mut buf = BytesMut::zeroed(offset);
+        buf.extend_from_slice(&value);
+        let len = buf.len();
+        handle.insert(key, Value::new(Data::String(buf.freeze()), None));
+        Ok(Frame::Integer(len as i64))
+    }
+
+    pub fn strlen(&mut self, key: Bytes) -> Result<Frame, ExecuteCommandError> {
+        let handle = &mut *self.store;
+        if let Some(mut val) = live_value(handle, &key, self.keyspace)? {
+            val.touch();
+            let data = val.data_mut().as_string_mut()?;
+            return Ok(Frame::Integer(data.len() as i64));
+        }
+        Ok(Frame::Integer(0))
+    }
+}
+
+impl Data {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Data::String(_) => "string",
+            Data::Json(_) => "json",
+            Data::HyperLogLog(_) => "hyperloglog",
+            Data::Bloom(_) => "bloom",
+        }
+    }
+
+    /// Size checked against the max value size of a keyspace, the length of strings and
+    /// of serialized documents and the size of the registers or bits of sketches.
+    pub fn size(&self) -> usize {
+        match self {
+            Data::String(data) => data.len(),
+            Data::Json(doc) => doc.to_string().len(),
+            Data::HyperLogLog(hll) => hll.heap_size(),
+            Data::Bloom(filter) => filter.heap_size(),
+        }
+    }
+
+    fn as_string_mut(&mut self) -> Result<&mut Bytes, ExecuteCommandError> {
+        match self {
+            Data::String(data) => Ok(data),
+            _ => Err(ExecuteCommandError::WrongType),
+        }
+    }
+
+    // strings holding an encoded hyperloglog (as returned by GET) are decoded in place,
+    // this allows hyperloglogs to be copied between keys and servers using GET and SET
+    fn as_hyperloglog_mut(&mut self) -> Result<&mut HyperLogLog, ExecuteCommandError> {
+        if let Data::String(data) = self {
+            let hll = HyperLogLog::from_bytes(data).map_err(|_| ExecuteCommandError::WrongType)?;
+            *self = Data::HyperLogLog(hll);
+        }
+        match self {
+            Data::HyperLogLog(hll) => Ok(hll),
+            _ => Err(ExecuteCommandError::WrongType),
+        }
+    }
+
+    fn as_bloom_mut(&mut self) -> Result<&mut BloomFilter, ExecuteCommandError> {
+        if let Data::String(data) = self {
+            let filter =
+                BloomFilter::from_bytes(data).map_err(|_| ExecuteCommandError::WrongType)?;
+            *self = Data::Bloom(filter);
+        }
+        match self {
+            Data::Bloom(filter) => Ok(filter),
+            _ => Err(ExecuteCommandError::WrongType),
+        }
+    }
+}
+
+// returns the key written by a command, used to invalidate the key for the clients
+// caching it. Conditional writes are included even if the condition did not hold.
+fn modified_key(command: &Command) -> Option<Bytes> {
+    match command {
+        Command::Set(cmd) => Some(cmd.key()),
+        Command::Del(cmd) => Some(cmd.key()),
+        Command::JsonSet(cmd) => Some(cmd.key()),
+        Command::JsonDel(cmd) => Some(cmd.key()),
+        Command::JsonIncrBy(cmd) => Some(cmd.key()),
+        Command::PfAdd(cmd) => Some(cmd.key()),
+        Command::PfMerge(cmd) => Some(cmd.destination()),
+        Command::BfReserve(cmd) => Some(cmd.key()),
+        Command::BfAdd(cmd) | Command::BfMAdd(cmd) => Some(cmd.key()),
+        Command::SetBit(cmd) => Some(cmd.key()),
+        Command::BitOp(cmd) => Some(cmd.destination()),
+        Command::Append(cmd) => Some(cmd.key()),
+        Command::SetRange(cmd) => Some(cmd.key()),
+        _ => None,
+    }
+}
+
+// picks the key to evict among a sample of the store according to the evictor, the
+// excluded key is never picked
+fn pick_victim(store: &Store, evictor: Evictor, exclude: Option<&Bytes>) -> Option<Bytes> {
+    let sample_size = MAX_MEMORY_EVICTOR_SAMPLE_SIZE as usize;
+    let candidates = store.iter().filter(|(key, _)| Some(*key) != exclude);
+    match evictor {
+        Evictor::Nop => None,
+        Evictor::Random => candidates
+            .take(sample_size)
+            .last()
+            .map(|(key, _)| key.clone()),
+        Evictor::Lru => candidates
+            .take(sample_size)
+            .min_by_key(|(_, value)| value.last_accessed())
+            .map(|(key, _)| key.clone()),
+    }
+}
+
+// reuses the stored bytes in place when this is the only reference to them
+fn take_mut(data: &mut Bytes) -> BytesMut {
+    std::mem::take(data)
+        .try_into_mut()
+        .unwrap_or_else(|data| BytesMut::from(&data[..]))
+}
+
+// visits every live key of the store with its estimated size. The store is locked for
+// a batch of keys at a time so that scanning a large keyspace does not block commands,
+// keys written after the scan started may be missed.
+async fn scan_memory_usage(
+    store: &Mutex<Store>,
+    mut visit: impl FnMut(&Bytes, &Value, usize),
+) -> Result<(), ExecuteCommandError> {
+    let keys: Vec<Bytes> = store.lock().keys().cloned().collect();
+    for batch in keys.chunks(SCAN_BATCH_SIZE) {
+        {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let handle = store.lock();
+            for key in batch {
+                match handle.get(key) {
+                    Some(val) if val.expire_at().is_some_and(|expiry| expiry <= now) => {}
+                    Some(val) => visit(key, val, entry_size(key, val)),
+                    None => {}
+                }
+            }
+        }
+        tokio::task::yield_now().await;
+    }
+    Ok(())
+}
+
+// a name and integer value pair of an INFO map
+fn field(name: &'static str, value: u64) -> [Frame; 2] {
+    [
+        Frame::String(Bytes::from_static(name.as_bytes())),
+        Frame::Integer(value as i64),
+    ]
+}
+
+// returns the value for the key, removing it first if it has already expired
+fn live_value<'a>(
+    handle: &'a mut Store,
+    key: &Bytes,
+    keyspace: &Keyspace,
+) -> Result<Option<ValueMut<'a>>, ExecuteCommandError> {
+    let expired = match handle.get(key).and_then(|val| val.expire_at()) {
+        Some(expiry) => expiry <= SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        None => false,
+    };
+    if expired {
+        expire(handle, key, &keyspace.stats, &keyspace.events);
+    }
+    Ok(handle.get_mut(key))
+}
+
+// removes an expired key, whether it is found by the expiring evictor or when it is
+// read, so that watchers and tracking clients are told about it either way
+fn expire(handle: &mut Store, key: &Bytes, stats: &KeyspaceStats, events: &KeyspaceEvents) {
+    if handle.remove(key).is_some() {
+        stats.record_expired();
+        events.emit(KeyspaceEvent::Expired, key);
+        events.invalidate(key);
+    }
+}
+
+impl Value {
+    pub fn new(data: Data, expire_at: Option<u64>) -> Self {
+        Value {
+            data,
+            last_accessed: Instant::now(),
+            expire_at,
+            version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    pub fn bump_version(&mut self) {
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn touch(&mut self) {
         self.last_accessed = Instant::now();
     }
 
-    pub fn data(&self) -> Bytes {
-        self.data.clone()
+    /// Estimated bytes used by the value, including the heap memory held by its data.
+    pub fn memory_usage(&self) -> usize {
+        let heap_size = match &self.data {
+            Data::String(data) => data.len(),
+            Data::Json(doc) => json::heap_size(doc),
+            Data::HyperLogLog(hll) => hll.heap_size(),
+            Data::Bloom(filter) => filter.heap_size(),
+        };
+        std::mem::size_of::<Value>() + heap_size
+    }
+
+    pub fn data(&self) -> &Data {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut Data {
+        &mut self.data
     }
 
     pub fn expire_at(&self) -> Option<u64> {
@@ -496,6 +1984,33 @@ impl Value {
     }
 }
 
+impl KeyspaceOptions {
+    pub fn new(options: &[KeyspaceOption]) -> Self {
+        let mut keyspace_options = KeyspaceOptions::default();
+        for option in options {
+            keyspace_options.apply(option);
+        }
+        keyspace_options
+    }
+
+    pub fn apply(&mut self, option: &KeyspaceOption) {
+        match *option {
+            KeyspaceOption::MaxKeys(max_keys) => self.max_keys = max_keys,
+            KeyspaceOption::MaxMemory(max_memory) => self.max_memory = max_memory,
+            KeyspaceOption::DefaultExpireAfter(millis) => self.default_expire_after = millis,
+            KeyspaceOption::MaxValueSize(max_value_size) => self.max_value_size = max_value_size,
+            KeyspaceOption::ReadOnly(read_only) => self.read_only = read_only,
+        }
+    }
+
+    fn check_value_size(&self, len: usize) -> Result<(), ExecuteCommandError> {
+        if self.max_value_size > 0 && len as u64 > self.max_value_size {
+            return Err(ExecuteCommandError::ValueTooLarge(self.max_value_size));
+        }
+        Ok(())
+    }
+}
+
 impl Evictor {
     pub fn as_bytes(&self) -> &[u8] {
         match self {
@@ -505,3 +2020,695 @@ impl Evictor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{cmd, TestDb};
+    use crate::tracking::TrackingMode;
+
+    async fn exec(db: &Db, args: &[&str]) -> Result<Frame, ExecuteCommandError> {
+        db.execute(cmd(args)).await
+    }
+
+    fn string(value: &str) -> Frame {
+        Frame::String(Bytes::copy_from_slice(value.as_bytes()))
+    }
+
+    async fn version(db: &Db, keyspace: &str, key: &str) -> String {
+        match exec(db, &["get", keyspace, key, "with", "version"])
+            .await
+            .unwrap()
+        {
+            Frame::Map(fields) => match fields[3] {
+                Frame::Integer(version) => version.to_string(),
+                ref frame => panic!("unexpected frame {:?}", frame),
+            },
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_transaction_given_keyspaces_applies_commands_atomically() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["create", "b"]).await.unwrap();
+        exec(&db, &["set", "a", "key", "0"]).await.unwrap();
+        exec(&db, &["set", "b", "key", "0"]).await.unwrap();
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..=500 {
+                    let value = i.to_string();
+                    db.execute_transaction(vec![
+                        cmd(&["set", "a", "key", &value]),
+                        cmd(&["set", "b", "key", &value]),
+                    ])
+                    .unwrap();
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..500 {
+                    let result = db
+                        .execute_transaction(vec![
+                            cmd(&["get", "a", "key"]),
+                            cmd(&["get", "b", "key"]),
+                        ])
+                        .unwrap();
+                    match result {
+                        Frame::Array(values) => assert_eq!(values[0], values[1]),
+                        frame => panic!("unexpected frame {:?}", frame),
+                    }
+                }
+            });
+        });
+
+        assert_eq!(
+            exec(&db, &["get", "a", "key"]).await.unwrap(),
+            string("500")
+        );
+        assert_eq!(
+            exec(&db, &["get", "b", "key"]).await.unwrap(),
+            string("500")
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_transaction_given_failing_command_applies_the_others() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+
+        let result = db
+            .execute_transaction(vec![
+                cmd(&["set", "a", "key", "value"]),
+                cmd(&["get", "missing", "key"]),
+            ])
+            .unwrap();
+        match result {
+            Frame::Array(values) => {
+                assert_eq!(values[0], Frame::Boolean(true));
+                assert!(matches!(values[1], Frame::Error(_)));
+            }
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+        assert_eq!(
+            exec(&db, &["get", "a", "key"]).await.unwrap(),
+            string("value")
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_transaction_given_crossing_transactions_does_not_deadlock() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["create", "b"]).await.unwrap();
+
+        // the keyspaces are used in opposite orders, locking them in command order
+        // would deadlock
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    db.execute_transaction(vec![
+                        cmd(&["set", "a", "key", "1"]),
+                        cmd(&["set", "b", "key", "1"]),
+                    ])
+                    .unwrap();
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    db.execute_transaction(vec![
+                        cmd(&["set", "b", "key", "2"]),
+                        cmd(&["set", "a", "key", "2"]),
+                    ])
+                    .unwrap();
+                }
+            });
+        });
+
+        let a = exec(&db, &["get", "a", "key"]).await.unwrap();
+        let b = exec(&db, &["get", "b", "key"]).await.unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[tokio::test]
+    async fn execute_transaction_given_changed_version_rejects_set() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "key", "1"]).await.unwrap();
+        let seen = version(&db, "a", "key").await;
+        exec(&db, &["set", "a", "key", "2"]).await.unwrap();
+
+        let result = db
+            .execute_transaction(vec![cmd(&["set", "a", "key", "3", "if", "version", &seen])])
+            .unwrap();
+        let current = version(&db, "a", "key").await;
+        assert_eq!(
+            result,
+            Frame::Array(vec![Frame::Error(Bytes::from(format!(
+                "version conflict, current version is {}",
+                current
+            )))])
+        );
+        assert_eq!(exec(&db, &["get", "a", "key"]).await.unwrap(), string("2"));
+    }
+
+    #[tokio::test]
+    async fn set_given_value_equals_sets_only_matching_value() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "lock", "owner-1"]).await.unwrap();
+
+        let result = exec(
+            &db,
+            &[
+                "set", "a", "lock", "owner-3", "if", "value", "equals", "owner-2",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Frame::Boolean(false));
+        assert_eq!(
+            exec(&db, &["get", "a", "lock"]).await.unwrap(),
+            string("owner-1")
+        );
+
+        let result = exec(
+            &db,
+            &[
+                "set", "a", "lock", "owner-2", "if", "value", "equals", "owner-1",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Frame::Boolean(true));
+        assert_eq!(
+            exec(&db, &["get", "a", "lock"]).await.unwrap(),
+            string("owner-2")
+        );
+    }
+
+    #[tokio::test]
+    async fn del_given_value_equals_deletes_only_matching_value() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "lock", "owner-1"]).await.unwrap();
+
+        let result = exec(
+            &db,
+            &["del", "a", "lock", "if", "value", "equals", "owner-2"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Frame::Boolean(false));
+        assert_eq!(
+            exec(&db, &["get", "a", "lock"]).await.unwrap(),
+            string("owner-1")
+        );
+
+        let result = exec(
+            &db,
+            &["del", "a", "lock", "if", "value", "equals", "owner-1"],
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, Frame::Boolean(true));
+        assert_eq!(exec(&db, &["get", "a", "lock"]).await.unwrap(), Frame::Null);
+    }
+
+    #[tokio::test]
+    async fn execute_given_failing_write_keeps_version() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "key", "value"]).await.unwrap();
+        let seen = version(&db, "a", "key").await;
+
+        for args in [
+            &["json.set", "a", "key", "$", "1"][..],
+            &["json.numincrby", "a", "key", "$", "1"],
+            &["pfadd", "a", "key", "element"],
+            &["bf.add", "a", "key", "item"],
+        ] {
+            assert!(matches!(
+                exec(&db, args).await,
+                Err(ExecuteCommandError::WrongType)
+            ));
+        }
+        assert_eq!(version(&db, "a", "key").await, seen);
+
+        let result = exec(&db, &["set", "a", "key", "new", "if", "version", &seen])
+            .await
+            .unwrap();
+        assert_eq!(result, Frame::Boolean(true));
+    }
+
+    #[tokio::test]
+    async fn execute_given_expired_key_read_emits_expired_and_invalidates() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "key", "value", "expire", "at", "1"])
+            .await
+            .unwrap();
+        exec(&db, &["set", "a", "other", "value", "expire", "at", "1"])
+            .await
+            .unwrap();
+
+        let (watcher, mut events) = Subscriber::new(8);
+        db.watch_events(&Bytes::from("a"), None, &watcher).unwrap();
+        let (reader, mut invalidations) = Subscriber::new(8);
+        db.tracker().enable(&reader, &TrackingMode::Default);
+        for key in ["key", "other"] {
+            db.tracker()
+                .track(&Bytes::from("a"), &Bytes::from(key), &reader);
+        }
+
+        // GET checks the expiry itself while TTL goes through the shared lookup
+        assert_eq!(exec(&db, &["get", "a", "key"]).await.unwrap(), Frame::Null);
+        assert_eq!(
+            exec(&db, &["ttl", "a", "other"]).await.unwrap(),
+            Frame::Null
+        );
+
+        for key in ["key", "other"] {
+            assert_eq!(
+                events.try_recv().unwrap(),
+                Frame::Map(vec![
+                    string("event"),
+                    string("expired"),
+                    string("keyspace"),
+                    string("a"),
+                    string("key"),
+                    string(key),
+                ])
+            );
+            assert_eq!(
+                invalidations.try_recv().unwrap(),
+                Frame::Array(vec![string("invalidate"), string("a"), string(key)])
+            );
+        }
+        assert!(events.try_recv().is_err());
+        assert_eq!(exec(&db, &["count", "a"]).await.unwrap(), Frame::Integer(0));
+    }
+
+    fn too_large(result: Result<Frame, ExecuteCommandError>, limit: u64) {
+        match result {
+            Err(ExecuteCommandError::ValueTooLarge(max)) => assert_eq!(max, limit),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_given_string_over_max_value_size_keeps_previous_value() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "max", "value", "size", "8"])
+            .await
+            .unwrap();
+        exec(&db, &["set", "a", "key", "12345678"]).await.unwrap();
+
+        too_large(exec(&db, &["setbit", "a", "key", "64", "1"]).await, 8);
+        assert_eq!(
+            exec(&db, &["get", "a", "key"]).await.unwrap(),
+            string("12345678")
+        );
+
+        exec(&db, &["alter", "a", "max", "value", "size", "4"])
+            .await
+            .unwrap();
+        too_large(exec(&db, &["bitop", "a", "or", "dest", "key"]).await, 4);
+        assert_eq!(exec(&db, &["get", "a", "dest"]).await.unwrap(), Frame::Null);
+    }
+
+    #[tokio::test]
+    async fn execute_given_json_over_max_value_size_keeps_previous_value() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "max", "value", "size", "16"])
+            .await
+            .unwrap();
+        exec(&db, &["json.set", "a", "doc", "$", "{\"a\":1}"])
+            .await
+            .unwrap();
+
+        too_large(
+            exec(&db, &["json.set", "a", "doc", "$.b", "\"0123456789\""]).await,
+            16,
+        );
+        too_large(
+            exec(&db, &["json.numincrby", "a", "doc", "$.a", "1000000000000"]).await,
+            16,
+        );
+        assert_eq!(
+            exec(&db, &["json.get", "a", "doc", "$"]).await.unwrap(),
+            string("{\"a\":1}")
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_given_write_over_max_value_size_emits_no_events() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "max", "value", "size", "16"])
+            .await
+            .unwrap();
+        exec(&db, &["json.set", "a", "doc", "$", "{\"a\":1}"])
+            .await
+            .unwrap();
+        let (watcher, mut events) = Subscriber::new(8);
+        db.watch_events(&Bytes::from("a"), None, &watcher).unwrap();
+        let (reader, mut invalidations) = Subscriber::new(8);
+        db.tracker().enable(&reader, &TrackingMode::Default);
+        db.tracker()
+            .track(&Bytes::from("a"), &Bytes::from("doc"), &reader);
+
+        too_large(exec(&db, &["setbit", "a", "bits", "128", "1"]).await, 16);
+        too_large(
+            exec(&db, &["json.set", "a", "doc", "$.b", "\"0123456789\""]).await,
+            16,
+        );
+
+        assert_eq!(exec(&db, &["count", "a"]).await.unwrap(), Frame::Integer(1));
+        assert!(events.try_recv().is_err());
+        assert!(invalidations.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn execute_given_hyperloglog_over_max_value_size_is_rejected() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "max", "value", "size", "1kb"])
+            .await
+            .unwrap();
+
+        too_large(exec(&db, &["pfadd", "a", "key", "element"]).await, 1024);
+        too_large(exec(&db, &["pfmerge", "a", "dest", "key"]).await, 1024);
+        assert_eq!(exec(&db, &["count", "a"]).await.unwrap(), Frame::Integer(0));
+    }
+
+    #[tokio::test]
+    async fn execute_given_bloom_filter_over_max_value_size_is_rejected() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "max", "value", "size", "1kb"])
+            .await
+            .unwrap();
+
+        exec(&db, &["bf.reserve", "a", "small", "100", "0.01"])
+            .await
+            .unwrap();
+        too_large(
+            exec(&db, &["bf.reserve", "a", "large", "100000", "0.01"]).await,
+            1024,
+        );
+
+        exec(&db, &["alter", "a", "max", "value", "size", "16"])
+            .await
+            .unwrap();
+        too_large(exec(&db, &["bf.add", "a", "other", "item"]).await, 16);
+        assert_eq!(exec(&db, &["count", "a"]).await.unwrap(), Frame::Integer(1));
+    }
+
+    fn snapshot(db: &Db, keyspace: &str) -> KeyspaceSnapshot {
+        db.keyspace_snapshots()
+            .into_iter()
+            .find(|(name, _)| name == keyspace)
+            .map(|(_, snapshot)| snapshot)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn execute_given_write_over_max_memory_evicts_least_recently_used() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "evictor", "lru"]).await.unwrap();
+        exec(&db, &["set", "a", "k1", "value"]).await.unwrap();
+        let entry = entry_size(&db, "a", "k1").await;
+        // room for two keys but not for a third
+        let max_memory = (entry * 2 + entry / 2).to_string();
+        exec(&db, &["alter", "a", "max", "memory", &max_memory])
+            .await
+            .unwrap();
+
+        exec(&db, &["set", "a", "k2", "value"]).await.unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        exec(&db, &["get", "a", "k1"]).await.unwrap();
+        exec(&db, &["set", "a", "k3", "value"]).await.unwrap();
+
+        assert_eq!(exec(&db, &["get", "a", "k2"]).await.unwrap(), Frame::Null);
+        assert_eq!(
+            exec(&db, &["get", "a", "k1"]).await.unwrap(),
+            string("value")
+        );
+        assert_eq!(
+            exec(&db, &["get", "a", "k3"]).await.unwrap(),
+            string("value")
+        );
+        assert_eq!(snapshot(&db, "a").evicted, 1);
+    }
+
+    async fn entry_size(db: &Db, keyspace: &str, key: &str) -> i64 {
+        match exec(db, &["memory", "usage", keyspace, key]).await.unwrap() {
+            Frame::Integer(entry) => entry,
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+    }
+
+    #[tokio::test]
+    async fn execute_given_write_over_max_memory_never_evicts_written_key() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "evictor", "random"])
+            .await
+            .unwrap();
+        exec(&db, &["set", "a", "k0", "value"]).await.unwrap();
+        // room for one key only
+        let max_memory = (entry_size(&db, "a", "k0").await * 3 / 2).to_string();
+        exec(&db, &["alter", "a", "max", "memory", &max_memory])
+            .await
+            .unwrap();
+
+        for i in 1..20 {
+            let key = format!("k{}", i);
+            exec(&db, &["set", "a", &key, "value"]).await.unwrap();
+            assert_eq!(
+                exec(&db, &["get", "a", &key]).await.unwrap(),
+                string("value")
+            );
+        }
+        assert_eq!(snapshot(&db, "a").keys, 1);
+    }
+
+    #[tokio::test]
+    async fn alter_given_lower_max_memory_evicts_over_quota() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "evictor", "lru"]).await.unwrap();
+        for key in ["k1", "k2", "k3"] {
+            exec(&db, &["set", "a", key, "value"]).await.unwrap();
+        }
+        // room for one key only
+        let max_memory = (entry_size(&db, "a", "k1").await * 3 / 2).to_string();
+
+        exec(&db, &["alter", "a", "max", "memory", &max_memory])
+            .await
+            .unwrap();
+
+        let altered = snapshot(&db, "a");
+        assert_eq!(altered.keys, 1);
+        assert_eq!(altered.evicted, 2);
+    }
+
+    #[tokio::test]
+    async fn max_memory_evictor_given_keyspace_with_quota_skips_it() {
+        let db = TestDb::new();
+        exec(
+            &db,
+            &["create", "quota", "evictor", "lru", "max", "memory", "1mb"],
+        )
+        .await
+        .unwrap();
+        exec(&db, &["create", "shared", "evictor", "lru"])
+            .await
+            .unwrap();
+        for key in ["k1", "k2", "k3"] {
+            exec(&db, &["set", "quota", key, "value"]).await.unwrap();
+            exec(&db, &["set", "shared", key, "value"]).await.unwrap();
+        }
+
+        db.evict();
+        for _ in 0..100 {
+            if snapshot(&db, "shared").evicted == 1 {
+                break;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        // gives the evictor of the keyspace with a quota the same chance to run
+        time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(snapshot(&db, "shared").keys, 2);
+        assert_eq!(snapshot(&db, "quota").keys, 3);
+        assert_eq!(snapshot(&db, "quota").evicted, 0);
+    }
+
+    #[tokio::test]
+    async fn rename_keyspace_given_existing_destination_replaces_it() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["create", "b"]).await.unwrap();
+        exec(&db, &["set", "a", "key", "next"]).await.unwrap();
+        exec(&db, &["set", "b", "key", "current"]).await.unwrap();
+        exec(&db, &["set", "b", "old", "current"]).await.unwrap();
+        let (reader, mut invalidations) = Subscriber::new(8);
+        db.tracker().enable(&reader, &TrackingMode::Default);
+        db.tracker()
+            .track(&Bytes::from("b"), &Bytes::from("old"), &reader);
+
+        assert_eq!(
+            exec(&db, &["rename", "keyspace", "a", "to", "b"])
+                .await
+                .unwrap(),
+            Frame::Boolean(true)
+        );
+
+        assert_eq!(
+            exec(&db, &["get", "b", "key"]).await.unwrap(),
+            string("next")
+        );
+        assert_eq!(exec(&db, &["get", "b", "old"]).await.unwrap(), Frame::Null);
+        assert_eq!(exec(&db, &["count", "b"]).await.unwrap(), Frame::Integer(1));
+        assert!(exec(&db, &["count", "a"]).await.is_err());
+        assert_eq!(
+            invalidations.try_recv().unwrap(),
+            Frame::Array(vec![string("invalidate"), string("b"), string("old")])
+        );
+        assert!(invalidations.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn copy_keyspace_given_keys_with_expiry_keeps_expiries_and_options() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a", "evictor", "lru", "max", "keys", "2"])
+            .await
+            .unwrap();
+        exec(
+            &db,
+            &["set", "a", "key", "value", "expire", "after", "60000"],
+        )
+        .await
+        .unwrap();
+
+        exec(&db, &["copy", "keyspace", "a", "to", "b"])
+            .await
+            .unwrap();
+
+        match exec(&db, &["ttl", "b", "key"]).await.unwrap() {
+            Frame::Integer(ttl) => assert!(ttl > 0 && ttl <= 60000),
+            frame => panic!("unexpected frame {:?}", frame),
+        }
+        assert_eq!(snapshot(&db, "b").expiring_keys, 1);
+        {
+            let handle = db.keyspaces.read();
+            let copy = &handle[&Bytes::from("b")];
+            assert_eq!(copy.evictor(), Evictor::Lru);
+            assert_eq!(copy.options().max_keys, 2);
+        }
+        exec(&db, &["set", "b", "other", "value"]).await.unwrap();
+        assert!(matches!(
+            exec(&db, &["set", "b", "third", "value"]).await,
+            Err(ExecuteCommandError::MaxKeysReached(2))
+        ));
+        assert_eq!(exec(&db, &["count", "a"]).await.unwrap(), Frame::Integer(1));
+    }
+
+    #[tokio::test]
+    async fn truncate_given_keys_resets_memory_and_invalidates_them() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "k1", "value"]).await.unwrap();
+        exec(
+            &db,
+            &["set", "a", "k2", "value", "expire", "after", "60000"],
+        )
+        .await
+        .unwrap();
+        let (reader, mut invalidations) = Subscriber::new(8);
+        db.tracker().enable(&reader, &TrackingMode::Default);
+        db.tracker()
+            .track(&Bytes::from("a"), &Bytes::from("k1"), &reader);
+
+        assert_eq!(
+            exec(&db, &["truncate", "a"]).await.unwrap(),
+            Frame::Integer(2)
+        );
+
+        let truncated = snapshot(&db, "a");
+        assert_eq!(truncated.keys, 0);
+        assert_eq!(truncated.expiring_keys, 0);
+        assert_eq!(truncated.used_memory, 0);
+        assert_eq!(
+            invalidations.try_recv().unwrap(),
+            Frame::Array(vec![string("invalidate"), string("a"), string("k1")])
+        );
+        assert!(invalidations.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn drop_given_tracked_keys_invalidates_them() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "k1", "value"]).await.unwrap();
+        exec(&db, &["set", "a", "k2", "value"]).await.unwrap();
+        let (reader, mut invalidations) = Subscriber::new(8);
+        db.tracker().enable(&reader, &TrackingMode::Default);
+        db.tracker()
+            .track(&Bytes::from("a"), &Bytes::from("k1"), &reader);
+
+        assert_eq!(
+            exec(&db, &["drop", "a"]).await.unwrap(),
+            Frame::Boolean(true)
+        );
+
+        assert_eq!(
+            invalidations.try_recv().unwrap(),
+            Frame::Array(vec![string("invalidate"), string("a"), string("k1")])
+        );
+        assert!(invalidations.try_recv().is_err());
+    }
+
+    fn event(event: &str, keyspace: &str, key: &str) -> Frame {
+        Frame::Map(vec![
+            string("event"),
+            string(event),
+            string("keyspace"),
+            string(keyspace),
+            string("key"),
+            string(key),
+        ])
+    }
+
+    #[tokio::test]
+    async fn execute_given_writes_of_any_type_emits_events_for_changes() {
+        let db = TestDb::new();
+        exec(&db, &["create", "a"]).await.unwrap();
+        exec(&db, &["set", "a", "empty", ""]).await.unwrap();
+        let (watcher, mut events) = Subscriber::new(16);
+        db.watch_events(&Bytes::from("a"), None, &watcher).unwrap();
+
+        exec(&db, &["json.set", "a", "doc", "$", "{\"a\":1}"])
+            .await
+            .unwrap();
+        exec(&db, &["json.numincrby", "a", "doc", "$.a", "1"])
+            .await
+            .unwrap();
+        exec(&db, &["json.del", "a", "doc", "$"]).await.unwrap();
+        exec(&db, &["pfadd", "a", "hll", "x"]).await.unwrap();
+        // adding an element that is already counted changes nothing
+        exec(&db, &["pfadd", "a", "hll", "x"]).await.unwrap();
+        exec(&db, &["bitop", "a", "or", "empty", "missing"])
+            .await
+            .unwrap();
+
+        for expected in [
+            event("set", "a", "doc"),
+            event("set", "a", "doc"),
+            event("del", "a", "doc"),
+            event("set", "a", "hll"),
+            event("del", "a", "empty"),
+        ] {
+            assert_eq!(events.try_recv().unwrap(), expected);
+        }
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,52 @@
+/// MurmurHash64A by Austin Appleby. Probabilistic structures persist positions derived from
+/// this hash, so unlike the std hasher it must stay stable across Rust versions and platforms.
+pub fn murmur64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().expect("chunk is 8 bytes long"));
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur64a_given_same_input_returns_same_hash() {
+        assert_eq!(murmur64a(b"segment", 0), murmur64a(b"segment", 0));
+    }
+
+    #[test]
+    fn murmur64a_given_different_seeds_returns_different_hashes() {
+        assert_ne!(murmur64a(b"segment", 0), murmur64a(b"segment", 1));
+    }
+
+    #[test]
+    fn murmur64a_given_empty_input_returns_hash() {
+        assert_eq!(murmur64a(b"", 0), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::hash::murmur64a;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use thiserror::Error;
+
+const MAGIC: &[u8] = b"HYLL";
+const VERSION: u8 = 1;
+// 2^14 registers give a standard error of ~0.81%
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+const REGISTER_BITS: usize = 6;
+const ENCODED_LEN: usize = MAGIC.len() + 1 + REGISTERS * REGISTER_BITS / 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HyperLogLog {
+    registers: Box<[u8]>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+#[error("invalid hyperloglog encoding")]
+pub struct DecodeError;
+
+impl HyperLogLog {
+    pub fn new() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTERS].into_boxed_slice(),
+        }
+    }
+
+    pub fn heap_size(&self) -> usize {
+        self.registers.len()
+    }
+
+    /// Adds an element, returns true if the estimated cardinality may have changed.
+    pub fn add(&mut self, element: &[u8]) -> bool {
+        let hash = murmur64a(element, 0);
+        let index = (hash & (REGISTERS as u64 - 1)) as usize;
+        // the guard bit makes sure that the rank never exceeds 64 - PRECISION + 1
+        let rank = ((hash >> PRECISION) | (1 << (64 - PRECISION))).trailing_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        let m = REGISTERS as f64;
+        let mut sum = 0.0;
+        let mut zeros = 0;
+        for register in self.registers.iter() {
+            sum += 1.0 / (1u64 << register) as f64;
+            if *register == 0 {
+                zeros += 1;
+            }
+        }
+
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let estimate = alpha * m * m / sum;
+        // small cardinalities are estimated a lot better using linear counting
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+
+    /// Encodes the registers as a magic header and version followed by the
+    /// registers packed as 6 bit integers.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(ENCODED_LEN);
+        buf.put_slice(MAGIC);
+        buf.put_u8(VERSION);
+        for chunk in self.registers.chunks(4) {
+            let packed = (chunk[0] as u32) << 18
+                | (chunk[1] as u32) << 12
+                | (chunk[2] as u32) << 6
+                | chunk[3] as u32;
+            buf.put_uint(packed as u64, 3);
+        }
+        buf.freeze()
+    }
+
+    pub fn from_bytes(mut data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() != ENCODED_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError);
+        }
+        data.advance(MAGIC.len());
+        if data.get_u8() != VERSION {
+            return Err(DecodeError);
+        }
+
+        let mut registers = Vec::with_capacity(REGISTERS);
+        while data.has_remaining() {
+            let packed = data.get_uint(3) as u32;
+            registers.push((packed >> 18) as u8 & 0x3f);
+            registers.push((packed >> 12) as u8 & 0x3f);
+            registers.push((packed >> 6) as u8 & 0x3f);
+            registers.push(packed as u8 & 0x3f);
+        }
+        Ok(HyperLogLog {
+            registers: registers.into_boxed_slice(),
+        })
+    }
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_given_empty_hll_returns_zero() {
+        assert_eq!(HyperLogLog::new().count(), 0);
+    }
+
+    #[test]
+    fn add_given_duplicate_element_returns_false() {
+        let mut hll = HyperLogLog::new();
+        assert!(hll.add(b"foo"));
+        assert!(!hll.add(b"foo"));
+        assert_eq!(hll.count(), 1);
+    }
+
+    #[test]
+    fn count_given_many_elements_returns_estimate_within_error() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..100_000 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+        let count = hll.count() as f64;
+        assert!((count - 100_000.0).abs() / 100_000.0 < 0.03);
+    }
+
+    #[test]
+    fn merge_given_overlapping_hlls_returns_union_estimate() {
+        let mut a = HyperLogLog::new();
+        let mut b = HyperLogLog::new();
+        for i in 0..1000 {
+            a.add(format!("element:{}", i).as_bytes());
+            b.add(format!("element:{}", i + 500).as_bytes());
+        }
+        a.merge(&b);
+        let count = a.count() as f64;
+        assert!((count - 1500.0).abs() / 1500.0 < 0.03);
+    }
+
+    #[test]
+    fn from_bytes_given_encoded_hll_returns_same_hll() {
+        let mut hll = HyperLogLog::new();
+        for i in 0..1000 {
+            hll.add(format!("element:{}", i).as_bytes());
+        }
+        let encoded = hll.to_bytes();
+        assert_eq!(encoded.len(), ENCODED_LEN);
+        assert_eq!(HyperLogLog::from_bytes(&encoded), Ok(hll));
+    }
+
+    #[test]
+    fn from_bytes_given_invalid_data_returns_decode_error() {
+        assert_eq!(HyperLogLog::from_bytes(b"foo"), Err(DecodeError));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::hash::murmur64a;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const DEPTH: usize = 4;
+const WIDTH: usize = 1024;
+/// Max number of hot keys remembered per keyspace.
+pub const TOP_K: usize = 32;
+// access counts are halved once per window so that keys that are no longer accessed
+// cool down and make room for the currently hot ones
+const WINDOW: Duration = Duration::from_secs(10);
+
+/// Approximate most frequently accessed keys of a keyspace. Access counts are
+/// estimated with a count-min sketch of fixed size and only the hottest keys are
+/// remembered, so the memory used does not grow with the number of keys.
+#[derive(Debug)]
+pub struct HotKeys {
+    counters: Vec<u32>,
+    top: HashMap<Bytes, u32>,
+    decayed_at: Instant,
+}
+
+impl HotKeys {
+    pub fn new() -> Self {
+        HotKeys {
+            counters: vec![0; DEPTH * WIDTH],
+            top: HashMap::with_capacity(TOP_K),
+            decayed_at: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, key: &Bytes) {
+        self.decay();
+
+        // positions are derived using double hashing like the bloom filter
+        let hash = murmur64a(key, 0);
+        let (h1, h2) = (hash as u32, (hash >> 32) as u32);
+        let mut estimate = u32::MAX;
+        for row in 0..DEPTH {
+            let column = h1.wrapping_add((row as u32).wrapping_mul(h2)) as usize % WIDTH;
+            let counter = &mut self.counters[row * WIDTH + column];
+            *counter = counter.saturating_add(1);
+            estimate = estimate.min(*counter);
+        }
+
+        if let Some(count) = self.top.get_mut(key) {
+            *count = estimate;
+            return;
+        }
+        if self.top.len() < TOP_K {
+            self.top.insert(key.clone(), estimate);
+            return;
+        }
+        let coldest = self
+            .top
+            .iter()
+            .min_by_key(|(_, count)| **count)
+            .filter(|(_, count)| **count < estimate)
+            .map(|(key, _)| key.clone());
+        if let Some(coldest) = coldest {
+            self.top.remove(&coldest);
+            self.top.insert(key.clone(), estimate);
+        }
+    }
+
+    /// Returns up to `limit` keys with their estimated number of accesses over the
+    /// recent windows, hottest first.
+    pub fn top(&mut self, limit: usize) -> Vec<(Bytes, u32)> {
+        self.decay();
+        let mut top: Vec<(Bytes, u32)> = self
+            .top
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .collect();
+        top.sort_by(|(a_key, a), (b_key, b)| b.cmp(a).then_with(|| a_key.cmp(b_key)));
+        top.truncate(limit);
+        top
+    }
+
+    // halves the counts once for every window that passed since the last decay
+    fn decay(&mut self) {
+        let windows = (self.decayed_at.elapsed().as_secs() / WINDOW.as_secs()) as u32;
+        if windows == 0 {
+            return;
+        }
+        let shift = windows.min(u32::BITS - 1);
+        for counter in self.counters.iter_mut() {
+            *counter >>= shift;
+        }
+        self.top.retain(|_, count| {
+            *count >>= shift;
+            *count > 0
+        });
+        self.decayed_at += WINDOW * windows;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_given_skewed_accesses_returns_hottest_first() {
+        let mut hotkeys = HotKeys::new();
+        for i in 0..1000 {
+            hotkeys.record(&Bytes::from(format!("cold:{}", i)));
+            if i % 2 == 0 {
+                hotkeys.record(&Bytes::from("hot"));
+            }
+            if i % 4 == 0 {
+                hotkeys.record(&Bytes::from("warm"));
+            }
+        }
+
+        let top = hotkeys.top(2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, Bytes::from("hot"));
+        assert!(top[0].1 >= 500);
+        assert_eq!(top[1].0, Bytes::from("warm"));
+        assert!(top[1].1 >= 250);
+    }
+
+    #[test]
+    fn top_given_elapsed_windows_decays_counts() {
+        let mut hotkeys = HotKeys::new();
+        for _ in 0..8 {
+            hotkeys.record(&Bytes::from("key"));
+        }
+        hotkeys.decayed_at -= WINDOW * 2;
+
+        assert_eq!(hotkeys.top(10), vec![(Bytes::from("key"), 2)]);
+    }
+}
@@ -0,0 +1,382 @@
+use crate::frame::Frame;
+use bytes::Bytes;
+use serde_json::{Number, Value};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum JsonError {
+    #[error("invalid json path '{0}'")]
+    InvalidPath(String),
+
+    #[error("new json documents must be created at the root path")]
+    NotRoot,
+
+    #[error("value at json path is not a number")]
+    NotANumber,
+
+    #[error("json number overflow")]
+    Overflow,
+
+    #[error("json array index out of range")]
+    IndexOutOfRange,
+}
+
+impl Path {
+    /// Parses a JSONPath like expression. Only a subset of JSONPath is supported:
+    /// `$` for the root, `.key` or `['key']` for object members and `[n]` for
+    /// array elements, negative indexes count from the end of the array.
+    pub fn parse(path: &str) -> Result<Path, JsonError> {
+        let invalid = || JsonError::InvalidPath(path.to_string());
+        let bytes = path.as_bytes();
+        if bytes.first() != Some(&b'$') {
+            return Err(invalid());
+        }
+
+        let mut segments = Vec::new();
+        let mut pos = 1;
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'.' => {
+                    let start = pos + 1;
+                    let mut end = start;
+                    while end < bytes.len() && bytes[end] != b'.' && bytes[end] != b'[' {
+                        end += 1;
+                    }
+                    if end == start {
+                        return Err(invalid());
+                    }
+                    segments.push(Segment::Key(path[start..end].to_string()));
+                    pos = end;
+                }
+                b'[' => {
+                    let close = path[pos..].find(']').ok_or_else(invalid)? + pos;
+                    let inner = &path[pos + 1..close];
+                    if inner.len() >= 2
+                        && ((inner.starts_with('\'') && inner.ends_with('\''))
+                            || (inner.starts_with('"') && inner.ends_with('"')))
+                    {
+                        segments.push(Segment::Key(inner[1..inner.len() - 1].to_string()));
+                    } else {
+                        let index = inner.parse::<i64>().map_err(|_| invalid())?;
+                        segments.push(Segment::Index(index));
+                    }
+                    pos = close + 1;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Path { segments })
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+fn resolve_index(len: usize, index: i64) -> Option<usize> {
+    let idx = if index < 0 { len as i64 + index } else { index };
+    if idx < 0 || idx >= len as i64 {
+        None
+    } else {
+        Some(idx as usize)
+    }
+}
+
+fn child<'a>(value: &'a Value, segment: &Segment) -> Option<&'a Value> {
+    match (value, segment) {
+        (Value::Object(map), Segment::Key(key)) => map.get(key),
+        (Value::Array(array), Segment::Index(index)) => {
+            resolve_index(array.len(), *index).map(|idx| &array[idx])
+        }
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, segment: &Segment) -> Option<&'a mut Value> {
+    match (value, segment) {
+        (Value::Object(map), Segment::Key(key)) => map.get_mut(key),
+        (Value::Array(array), Segment::Index(index)) => {
+            resolve_index(array.len(), *index).map(move |idx| &mut array[idx])
+        }
+        _ => None,
+    }
+}
+
+pub fn get<'a>(doc: &'a Value, path: &Path) -> Option<&'a Value> {
+    let mut current = doc;
+    for segment in &path.segments {
+        current = child(current, segment)?;
+    }
+    Some(current)
+}
+
+pub fn get_mut<'a>(doc: &'a mut Value, path: &Path) -> Option<&'a mut Value> {
+    let mut current = doc;
+    for segment in &path.segments {
+        current = child_mut(current, segment)?;
+    }
+    Some(current)
+}
+
+/// Sets the value at the given path, returns false if the parent of the path does not exist.
+/// Object members are created if missing, array elements must already exist.
+pub fn set(doc: &mut Value, path: &Path, value: Value) -> Result<bool, JsonError> {
+    let (last, parents) = match path.segments.split_last() {
+        Some(split) => split,
+        None => {
+            *doc = value;
+            return Ok(true);
+        }
+    };
+
+    let mut parent = doc;
+    for segment in parents {
+        parent = match child_mut(parent, segment) {
+            Some(value) => value,
+            None => return Ok(false),
+        };
+    }
+
+    match (parent, last) {
+        (Value::Object(map), Segment::Key(key)) => {
+            map.insert(key.clone(), value);
+            Ok(true)
+        }
+        (Value::Array(array), Segment::Index(index)) => {
+            let idx = resolve_index(array.len(), *index).ok_or(JsonError::IndexOutOfRange)?;
+            array[idx] = value;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Deletes the value at the given path, returns false if nothing was deleted.
+/// The root can't be deleted using this function, callers should delete the whole document instead.
+pub fn del(doc: &mut Value, path: &Path) -> bool {
+    let (last, parents) = match path.segments.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    let mut parent = doc;
+    for segment in parents {
+        parent = match child_mut(parent, segment) {
+            Some(value) => value,
+            None => return false,
+        };
+    }
+
+    match (parent, last) {
+        (Value::Object(map), Segment::Key(key)) => map.remove(key).is_some(),
+        (Value::Array(array), Segment::Index(index)) => match resolve_index(array.len(), *index) {
+            Some(idx) => {
+                array.remove(idx);
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Increments the number at the given path by `by` and returns the new number.
+/// Integers stay integers as long as both operands are integers and the result fits in an i64.
+pub fn incr_by(doc: &mut Value, path: &Path, by: &Number) -> Result<Option<Number>, JsonError> {
+    let target = match get_mut(doc, path) {
+        Some(target) => target,
+        None => return Ok(None),
+    };
+
+    let current = match target {
+        Value::Number(number) => number,
+        _ => return Err(JsonError::NotANumber),
+    };
+
+    let result = match (current.as_i64(), by.as_i64()) {
+        (Some(a), Some(b)) => Number::from(a.checked_add(b).ok_or(JsonError::Overflow)?),
+        _ => {
+            let a = current.as_f64().ok_or(JsonError::NotANumber)?;
+            let b = by.as_f64().ok_or(JsonError::NotANumber)?;
+            Number::from_f64(a + b).ok_or(JsonError::Overflow)?
+        }
+    };
+
+    *current = result.clone();
+    Ok(Some(result))
+}
+
+/// Estimates the heap memory held by a document, the value itself is not included.
+pub fn heap_size(value: &Value) -> usize {
+    match value {
+        Value::String(string) => string.capacity(),
+        Value::Array(items) => {
+            items.capacity() * std::mem::size_of::<Value>()
+                + items.iter().map(heap_size).sum::<usize>()
+        }
+        Value::Object(members) => members
+            .iter()
+            .map(|(key, value)| {
+                std::mem::size_of::<(String, Value)>() + key.capacity() + heap_size(value)
+            })
+            .sum(),
+        _ => 0,
+    }
+}
+
+pub fn number_to_frame(number: &Number) -> Frame {
+    match number.as_i64() {
+        Some(int) => Frame::Integer(int),
+        None => Frame::Double(number.as_f64().unwrap_or(f64::NAN)),
+    }
+}
+
+/// Converts a JSON value into a native frame tree, objects become maps,
+/// arrays become arrays and numbers become integers or doubles.
+pub fn to_frame(value: &Value) -> Frame {
+    match value {
+        Value::Null => Frame::Null,
+        Value::Bool(data) => Frame::Boolean(*data),
+        Value::Number(number) => number_to_frame(number),
+        Value::String(data) => Frame::String(Bytes::copy_from_slice(data.as_bytes())),
+        Value::Array(array) => Frame::Array(array.iter().map(to_frame).collect()),
+        Value::Object(map) => {
+            let mut frames = Vec::with_capacity(map.len() * 2);
+            for (key, value) in map {
+                frames.push(Frame::String(Bytes::copy_from_slice(key.as_bytes())));
+                frames.push(to_frame(value));
+            }
+            Frame::Map(frames)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_given_root_returns_empty_path() {
+        assert!(Path::parse("$").unwrap().is_root());
+    }
+
+    #[test]
+    fn parse_given_path_without_root_returns_invalid_path_error() {
+        assert!(Path::parse("foo.bar").is_err());
+    }
+
+    #[test]
+    fn parse_given_dotted_and_indexed_path_returns_segments() {
+        assert_eq!(
+            Path::parse("$.foo[1]['bar baz'].qux[-1]").unwrap().segments,
+            vec![
+                Segment::Key("foo".to_string()),
+                Segment::Index(1),
+                Segment::Key("bar baz".to_string()),
+                Segment::Key("qux".to_string()),
+                Segment::Index(-1),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_given_empty_key_returns_invalid_path_error() {
+        assert!(Path::parse("$..foo").is_err());
+    }
+
+    #[test]
+    fn parse_given_unterminated_index_returns_invalid_path_error() {
+        assert!(Path::parse("$.foo[1").is_err());
+    }
+
+    #[test]
+    fn get_given_nested_path_returns_value() {
+        let doc = json!({"foo": [1, {"bar": true}]});
+        let path = Path::parse("$.foo[-1].bar").unwrap();
+        assert_eq!(get(&doc, &path), Some(&json!(true)));
+    }
+
+    #[test]
+    fn set_given_missing_member_creates_member() {
+        let mut doc = json!({"foo": {}});
+        let path = Path::parse("$.foo.bar").unwrap();
+        assert_eq!(set(&mut doc, &path, json!(1)), Ok(true));
+        assert_eq!(doc, json!({"foo": {"bar": 1}}));
+    }
+
+    #[test]
+    fn set_given_missing_parent_returns_false() {
+        let mut doc = json!({});
+        let path = Path::parse("$.foo.bar").unwrap();
+        assert_eq!(set(&mut doc, &path, json!(1)), Ok(false));
+    }
+
+    #[test]
+    fn del_given_array_element_removes_element() {
+        let mut doc = json!({"foo": [1, 2, 3]});
+        let path = Path::parse("$.foo[0]").unwrap();
+        assert!(del(&mut doc, &path));
+        assert_eq!(doc, json!({"foo": [2, 3]}));
+    }
+
+    #[test]
+    fn incr_by_given_integers_returns_integer() {
+        let mut doc = json!({"foo": 1});
+        let path = Path::parse("$.foo").unwrap();
+        let result = incr_by(&mut doc, &path, &Number::from(2)).unwrap();
+        assert_eq!(result, Some(Number::from(3)));
+    }
+
+    #[test]
+    fn incr_by_given_double_returns_double() {
+        let mut doc = json!({"foo": 1});
+        let path = Path::parse("$.foo").unwrap();
+        let by = Number::from_f64(0.5).unwrap();
+        let result = incr_by(&mut doc, &path, &by).unwrap();
+        assert_eq!(result, Number::from_f64(1.5));
+    }
+
+    #[test]
+    fn incr_by_given_non_number_returns_not_a_number_error() {
+        let mut doc = json!({"foo": "bar"});
+        let path = Path::parse("$.foo").unwrap();
+        assert_eq!(
+            incr_by(&mut doc, &path, &Number::from(1)),
+            Err(JsonError::NotANumber)
+        );
+    }
+
+    #[test]
+    fn to_frame_given_object_returns_map() {
+        let doc = json!({"foo": [1, 2.5, null]});
+        assert_eq!(
+            to_frame(&doc),
+            Frame::Map(vec![
+                Frame::String(Bytes::from("foo")),
+                Frame::Array(vec![Frame::Integer(1), Frame::Double(2.5), Frame::Null]),
+            ])
+        );
+    }
+
+    #[test]
+    fn heap_size_given_nested_document_counts_strings_and_members() {
+        assert_eq!(heap_size(&json!(1)), 0);
+        let small = heap_size(&json!({"a": "b"}));
+        let large = heap_size(&json!({"a": "b".repeat(1000)}));
+        assert!(small > 0);
+        assert_eq!(large - small, 999);
+    }
+}
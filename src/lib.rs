@@ -1,6 +1,25 @@
+mod acl;
+mod bitmap;
+mod bloom;
+mod clients;
 mod command;
 pub mod config;
 mod connection;
 mod db;
 mod frame;
+mod hash;
+mod hll;
+mod hotkeys;
+mod json;
+mod metrics;
+mod monitor;
+mod pattern;
+mod pubsub;
 pub mod server;
+mod slowlog;
+mod stats;
+mod store;
+#[cfg(test)]
+mod test_util;
+mod tls;
+mod tracking;
@@ -0,0 +1,375 @@
+use crate::db::Db;
+use crate::stats::LATENCY_BUCKETS;
+use crossbeam::sync::WaitGroup;
+use std::fmt::Write;
+use std::io;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+// requests are only ever a request line and a few headers, anything larger is dropped
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Serves the metrics in the Prometheus text format on `/metrics` until the server
+/// shuts down. Every request is answered on its own connection which is then closed.
+pub async fn serve(ln: TcpListener, db: Arc<Db>, mut done: broadcast::Receiver<()>, wg: WaitGroup) {
+    loop {
+        tokio::select! {
+            maybe_connection = ln.accept() => {
+                let stream = match maybe_connection {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        error!("failed to accept metrics connection, error = {}", e);
+                        continue;
+                    }
+                };
+                let db = db.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = respond(stream, &db).await {
+                        debug!("failed to serve metrics, error = {}", e);
+                    }
+                });
+            }
+            _ = done.recv() => {
+                debug!("stopping metrics listener, shutdown signal received");
+                break;
+            }
+        }
+    }
+    drop(wg);
+}
+
+async fn respond(mut stream: TcpStream, db: &Db) -> io::Result<()> {
+    let mut request = Vec::with_capacity(1024);
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || request.len() + n > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = request.split(|b| *b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|b| *b == b' ');
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some(b"GET"), Some(b"/metrics")) => ("200 OK", render(db)),
+        (Some(b"GET"), Some(_)) => ("404 Not Found", String::new()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Renders the server and keyspace metrics in the Prometheus text format.
+pub fn render(db: &Db) -> String {
+    let stats = db.stats();
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "segment_uptime_seconds",
+        "gauge",
+        "Seconds since the server started.",
+    );
+    let _ = writeln!(out, "segment_uptime_seconds {}", stats.uptime_secs());
+    metric(
+        &mut out,
+        "segment_connected_clients",
+        "gauge",
+        "Connections currently open.",
+    );
+    let _ = writeln!(out, "segment_connected_clients {}", db.clients().len());
+    metric(
+        &mut out,
+        "segment_connections_total",
+        "counter",
+        "Connections accepted.",
+    );
+    let _ = writeln!(out, "segment_connections_total {}", stats.connections());
+    metric(
+        &mut out,
+        "segment_rejected_connections_total",
+        "counter",
+        "Connections rejected because max_clients was reached.",
+    );
+    let _ = writeln!(
+        out,
+        "segment_rejected_connections_total {}",
+        stats.rejected_connections()
+    );
+    metric(
+        &mut out,
+        "segment_used_memory_bytes",
+        "gauge",
+        "Memory used by the server process.",
+    );
+    let _ = writeln!(out, "segment_used_memory_bytes {}", stats.used_memory());
+    metric(
+        &mut out,
+        "segment_max_memory_bytes",
+        "gauge",
+        "Configured max_memory, 0 when unlimited.",
+    );
+    let _ = writeln!(out, "segment_max_memory_bytes {}", stats.max_memory());
+
+    let commands = stats.commands();
+    metric(
+        &mut out,
+        "segment_commands_total",
+        "counter",
+        "Commands processed by command.",
+    );
+    for (name, command) in &commands {
+        let _ = writeln!(
+            out,
+            "segment_commands_total{{command=\"{}\"}} {}",
+            name,
+            command.calls()
+        );
+    }
+    metric(
+        &mut out,
+        "segment_command_duration_seconds",
+        "histogram",
+        "Time taken to run commands by command.",
+    );
+    for (name, command) in &commands {
+        let buckets = command.cumulative_buckets();
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&buckets) {
+            let _ = writeln!(
+                out,
+                "segment_command_duration_seconds_bucket{{command=\"{}\",le=\"{}\"}} {}",
+                name, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "segment_command_duration_seconds_bucket{{command=\"{}\",le=\"+Inf\"}} {}",
+            name,
+            buckets.last().copied().unwrap_or_default()
+        );
+        let _ = writeln!(
+            out,
+            "segment_command_duration_seconds_sum{{command=\"{}\"}} {}",
+            name,
+            command.latency_sum().as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "segment_command_duration_seconds_count{{command=\"{}\"}} {}",
+            name,
+            command.calls()
+        );
+    }
+
+    let keyspaces: Vec<(String, _)> = db
+        .keyspace_snapshots()
+        .into_iter()
+        .map(|(name, snapshot)| (escape(&name), snapshot))
+        .collect();
+    metric(
+        &mut out,
+        "segment_keyspace_keys",
+        "gauge",
+        "Keys stored in the keyspace.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_keys{{keyspace=\"{}\"}} {}",
+            name, snapshot.keys
+        );
+    }
+    metric(
+        &mut out,
+        "segment_keyspace_expiring_keys",
+        "gauge",
+        "Keys with an expiry stored in the keyspace.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_expiring_keys{{keyspace=\"{}\"}} {}",
+            name, snapshot.expiring_keys
+        );
+    }
+    metric(
+        &mut out,
+        "segment_keyspace_used_memory_bytes",
+        "gauge",
+        "Estimated memory used by the keys of the keyspace.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_used_memory_bytes{{keyspace=\"{}\"}} {}",
+            name, snapshot.used_memory
+        );
+    }
+    metric(
+        &mut out,
+        "segment_keyspace_max_memory_bytes",
+        "gauge",
+        "Memory quota of the keyspace, 0 when unlimited.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_max_memory_bytes{{keyspace=\"{}\"}} {}",
+            name, snapshot.max_memory
+        );
+    }
+    metric(
+        &mut out,
+        "segment_keyspace_hits_total",
+        "counter",
+        "Reads that found the key.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_hits_total{{keyspace=\"{}\"}} {}",
+            name, snapshot.hits
+        );
+    }
+    metric(
+        &mut out,
+        "segment_keyspace_misses_total",
+        "counter",
+        "Reads that did not find the key.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_misses_total{{keyspace=\"{}\"}} {}",
+            name, snapshot.misses
+        );
+    }
+    metric(
+        &mut out,
+        "segment_expired_keys_total",
+        "counter",
+        "Keys removed once expired.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_expired_keys_total{{keyspace=\"{}\"}} {}",
+            name, snapshot.expired
+        );
+    }
+    metric(
+        &mut out,
+        "segment_evicted_keys_total",
+        "counter",
+        "Keys evicted by the keyspace evictor to free memory.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_evicted_keys_total{{keyspace=\"{}\"}} {}",
+            name, snapshot.evicted
+        );
+    }
+    // the evictor can be replaced, so it is reported on its own rather than as a label
+    // of the counters
+    metric(
+        &mut out,
+        "segment_keyspace_evictor_info",
+        "gauge",
+        "Evictor currently used by the keyspace.",
+    );
+    for (name, snapshot) in &keyspaces {
+        let _ = writeln!(
+            out,
+            "segment_keyspace_evictor_info{{keyspace=\"{}\",evictor=\"{}\"}} 1",
+            name,
+            String::from_utf8_lossy(snapshot.evictor.as_bytes()).to_lowercase()
+        );
+    }
+    out
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// escapes a keyspace name for use as a label value
+fn escape(name: &[u8]) -> String {
+    String::from_utf8_lossy(name)
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{cmd, TestDb};
+    use std::time::Duration;
+
+    async fn scrape(port: u16, path: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn escape_given_quotes_and_newlines_escapes_them() {
+        assert_eq!(escape(b"a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+
+    #[tokio::test]
+    async fn serve_given_metrics_path_returns_command_metrics() {
+        let db = TestDb::new();
+        db.stats().record_command("get", Duration::from_micros(300));
+        let ln = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = ln.local_addr().unwrap().port();
+        tokio::spawn(serve(ln, db.arc(), db.done(), WaitGroup::new()));
+
+        let response = scrape(port, "/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("segment_commands_total{command=\"get\"} 1\n"));
+        assert!(response.contains(
+            "segment_command_duration_seconds_bucket{command=\"get\",le=\"0.0005\"} 1\n"
+        ));
+        assert!(response.contains(
+            "segment_command_duration_seconds_bucket{command=\"get\",le=\"0.00025\"} 0\n"
+        ));
+
+        let response = scrape(port, "/").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+
+    #[tokio::test]
+    async fn render_given_replaced_evictor_reports_it_apart_from_evictions() {
+        let db = TestDb::new();
+        for args in [
+            &["create", "ks", "evictor", "lru"][..],
+            &["alter", "ks", "evictor", "random"],
+        ] {
+            db.execute(cmd(args)).await.unwrap();
+        }
+
+        let out = render(&db);
+        assert!(out.contains("segment_evicted_keys_total{keyspace=\"ks\"} 0\n"));
+        assert!(
+            out.contains("segment_keyspace_evictor_info{keyspace=\"ks\",evictor=\"random\"} 1\n")
+        );
+        assert!(!out.contains("evictor=\"lru\""));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::frame::Frame;
+use crate::pubsub::Subscriber;
+use bytes::Bytes;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Connections that ran `MONITOR` and are sent every command the server receives.
+/// Feeding is a single atomic load when nobody is monitoring.
+#[derive(Debug, Default)]
+pub struct Monitors {
+    monitors: RwLock<HashMap<u64, Subscriber>>,
+    active: AtomicUsize,
+}
+
+impl Monitors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, subscriber: &Subscriber) {
+        let mut handle = self.monitors.write();
+        handle.insert(subscriber.id(), subscriber.clone());
+        self.active.store(handle.len(), Ordering::Relaxed);
+    }
+
+    pub fn remove(&self, subscriber: &Subscriber) {
+        let mut handle = self.monitors.write();
+        handle.remove(&subscriber.id());
+        self.active.store(handle.len(), Ordering::Relaxed);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed) > 0
+    }
+
+    /// Pushes the command to every monitor, `args` are the arguments of the command
+    /// as received from the client.
+    pub fn feed(&self, client: &str, keyspace: Option<Bytes>, command: &str, args: &[Bytes]) {
+        if !self.is_active() {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default();
+        let frame = || {
+            Frame::Map(vec![
+                Frame::String(Bytes::from_static(b"timestamp")),
+                Frame::Double(timestamp),
+                Frame::String(Bytes::from_static(b"client")),
+                Frame::String(Bytes::from(client.to_string())),
+                Frame::String(Bytes::from_static(b"keyspace")),
+                keyspace.clone().map(Frame::String).unwrap_or(Frame::Null),
+                Frame::String(Bytes::from_static(b"command")),
+                Frame::String(Bytes::from(command.to_string())),
+                Frame::String(Bytes::from_static(b"args")),
+                Frame::Array(args.iter().cloned().map(Frame::String).collect()),
+            ])
+        };
+        for subscriber in self.monitors.read().values() {
+            subscriber.push(frame());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feed_given_monitor_pushes_command() {
+        let monitors = Monitors::new();
+        let (subscriber, mut rx) = Subscriber::new(8);
+        monitors.feed("127.0.0.1:5000", None, "ping", &[]);
+        assert!(rx.try_recv().is_err());
+
+        monitors.add(&subscriber);
+        monitors.feed(
+            "127.0.0.1:5000",
+            Some(Bytes::from("ks")),
+            "get",
+            &[Bytes::from("ks"), Bytes::from("key")],
+        );
+        let Ok(Frame::Map(fields)) = rx.try_recv() else {
+            panic!("expected a map");
+        };
+        assert_eq!(fields[3], Frame::String(Bytes::from("127.0.0.1:5000")));
+        assert_eq!(fields[5], Frame::String(Bytes::from("ks")));
+        assert_eq!(fields[7], Frame::String(Bytes::from("get")));
+        assert_eq!(
+            fields[9],
+            Frame::Array(vec![
+                Frame::String(Bytes::from("ks")),
+                Frame::String(Bytes::from("key"))
+            ])
+        );
+
+        monitors.remove(&subscriber);
+        assert!(!monitors.is_active());
+    }
+}
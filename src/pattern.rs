@@ -0,0 +1,140 @@
+/// Matches a subject against a glob style pattern. `*` matches any sequence of bytes,
+/// `?` matches a single byte, `[abc]`, `[a-z]` and `[^a]` match byte classes and `\`
+/// escapes the next byte.
+pub fn matches(pattern: &[u8], subject: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // position of the last `*` in the pattern and the subject position it was tried at,
+    // used to backtrack without recursion
+    let mut star: Option<(usize, usize)> = None;
+
+    while s < subject.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    star = Some((p, s));
+                    p += 1;
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    s += 1;
+                    continue;
+                }
+                b'[' => {
+                    if let Some((matched, len)) = match_class(&pattern[p..], subject[s]) {
+                        if matched {
+                            p += len;
+                            s += 1;
+                            continue;
+                        }
+                    } else if subject[s] == b'[' {
+                        // an unterminated class is matched literally
+                        p += 1;
+                        s += 1;
+                        continue;
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == subject[s] {
+                        p += 2;
+                        s += 1;
+                        continue;
+                    }
+                }
+                byte => {
+                    if byte == subject[s] {
+                        p += 1;
+                        s += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+
+        match star {
+            Some((star_p, star_s)) => {
+                p = star_p + 1;
+                s = star_s + 1;
+                star = Some((star_p, star_s + 1));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|byte| *byte == b'*')
+}
+
+// returns whether the byte matches the class at the start of the pattern and the
+// length of the class, or None if the class is not terminated
+fn match_class(pattern: &[u8], byte: u8) -> Option<(bool, usize)> {
+    let mut idx = 1;
+    let negated = pattern.get(idx) == Some(&b'^');
+    if negated {
+        idx += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    loop {
+        let current = *pattern.get(idx)?;
+        if current == b']' && !first {
+            return Some((matched != negated, idx + 1));
+        }
+        first = false;
+
+        let current = if current == b'\\' {
+            idx += 1;
+            *pattern.get(idx)?
+        } else {
+            current
+        };
+
+        if pattern.get(idx + 1) == Some(&b'-') && pattern.get(idx + 2).is_some_and(|b| *b != b']') {
+            let end = pattern[idx + 2];
+            let (low, high) = if current <= end {
+                (current, end)
+            } else {
+                (end, current)
+            };
+            matched |= (low..=high).contains(&byte);
+            idx += 3;
+        } else {
+            matched |= current == byte;
+            idx += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_given_wildcards_matches_any_sequence() {
+        assert!(matches(b"news.*", b"news.sports"));
+        assert!(matches(b"news.*", b"news."));
+        assert!(matches(b"*", b""));
+        assert!(matches(b"a*b*c", b"axxbyyc"));
+        assert!(!matches(b"a*b*c", b"axxbyy"));
+        assert!(matches(b"h?llo", b"hello"));
+        assert!(!matches(b"h?llo", b"hllo"));
+    }
+
+    #[test]
+    fn matches_given_classes_matches_class_members() {
+        assert!(matches(b"h[ae]llo", b"hallo"));
+        assert!(!matches(b"h[ae]llo", b"hillo"));
+        assert!(matches(b"h[^e]llo", b"hallo"));
+        assert!(!matches(b"h[^e]llo", b"hello"));
+        assert!(matches(b"user:[0-9]", b"user:7"));
+        assert!(!matches(b"user:[0-9]", b"user:x"));
+    }
+
+    #[test]
+    fn matches_given_escaped_bytes_matches_literally() {
+        assert!(matches(b"what\\?", b"what?"));
+        assert!(!matches(b"what\\?", b"whats"));
+        assert!(matches(b"a\\*", b"a*"));
+        assert!(!matches(b"a\\*", b"ab"));
+    }
+}
@@ -0,0 +1,332 @@
+use crate::frame::Frame;
+use crate::pattern;
+use crate::tracking::Tracker;
+use bytes::Bytes;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::Notify;
+
+static NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Sending half of a connection's push channel. Pushes never block the publisher,
+/// once the buffer of a subscriber is full it is marked as overflowed and the
+/// connection is expected to disconnect it.
+#[derive(Debug, Clone)]
+pub struct Subscriber {
+    id: u64,
+    tx: mpsc::Sender<Frame>,
+    overflowed: Arc<AtomicBool>,
+    overflow: Arc<Notify>,
+}
+
+#[derive(Debug, Default)]
+pub struct PubSub {
+    channels: RwLock<HashMap<Bytes, HashMap<u64, Subscriber>>>,
+    patterns: RwLock<HashMap<Bytes, HashMap<u64, Subscriber>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyspaceEvent {
+    Set,
+    Del,
+    Expired,
+    Evicted,
+}
+
+/// Watchers of the changes made to a keyspace. Emitting is a single atomic load
+/// when nobody is watching so it can be done from every write path.
+#[derive(Debug)]
+pub struct KeyspaceEvents {
+    keyspace: RwLock<Bytes>,
+    tracker: Arc<Tracker>,
+    watchers: RwLock<HashMap<u64, (Subscriber, Option<Bytes>)>>,
+    watching: AtomicUsize,
+}
+
+impl Subscriber {
+    pub fn new(buffer_size: usize) -> (Subscriber, mpsc::Receiver<Frame>) {
+        let (tx, rx) = mpsc::channel(buffer_size);
+        let subscriber = Subscriber {
+            id: NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::Relaxed),
+            tx,
+            overflowed: Arc::new(AtomicBool::new(false)),
+            overflow: Arc::new(Notify::new()),
+        };
+        (subscriber, rx)
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Queues a frame for the subscriber, returns false if it could not be queued.
+    pub fn push(&self, frame: Frame) -> bool {
+        match self.tx.try_send(frame) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                if !self.overflowed.swap(true, Ordering::Relaxed) {
+                    self.overflow.notify_one();
+                }
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// Number of frames queued and not yet written to the connection.
+    pub fn pending(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    pub fn overflowed(&self) -> bool {
+        self.overflowed.load(Ordering::Relaxed)
+    }
+
+    /// Completes once the buffer of the subscriber has overflowed, this lets the
+    /// connection give up on a write to a client that stopped reading.
+    pub async fn wait_for_overflow(&self) {
+        self.overflow.notified().await
+    }
+}
+
+impl PubSub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, channel: Bytes, subscriber: &Subscriber) {
+        let mut handle = self.channels.write();
+        handle
+            .entry(channel)
+            .or_default()
+            .insert(subscriber.id(), subscriber.clone());
+    }
+
+    pub fn unsubscribe(&self, channel: &Bytes, subscriber: &Subscriber) {
+        let mut handle = self.channels.write();
+        remove(&mut handle, channel, subscriber.id());
+    }
+
+    pub fn psubscribe(&self, pattern: Bytes, subscriber: &Subscriber) {
+        let mut handle = self.patterns.write();
+        handle
+            .entry(pattern)
+            .or_default()
+            .insert(subscriber.id(), subscriber.clone());
+    }
+
+    pub fn punsubscribe(&self, pattern: &Bytes, subscriber: &Subscriber) {
+        let mut handle = self.patterns.write();
+        remove(&mut handle, pattern, subscriber.id());
+    }
+
+    /// Publishes a message to the subscribers of the channel and of every matching
+    /// pattern, returns the number of subscribers the message was queued for.
+    pub fn publish(&self, channel: &Bytes, message: &Bytes) -> usize {
+        let mut receivers = 0;
+        if let Some(subscribers) = self.channels.read().get(channel) {
+            for subscriber in subscribers.values() {
+                let frame = Frame::Array(vec![
+                    Frame::String(Bytes::from_static(b"message")),
+                    Frame::String(channel.clone()),
+                    Frame::String(message.clone()),
+                ]);
+                if subscriber.push(frame) {
+                    receivers += 1;
+                }
+            }
+        }
+
+        for (pattern, subscribers) in self.patterns.read().iter() {
+            if !pattern::matches(pattern, channel) {
+                continue;
+            }
+            for subscriber in subscribers.values() {
+                let frame = Frame::Array(vec![
+                    Frame::String(Bytes::from_static(b"pmessage")),
+                    Frame::String(pattern.clone()),
+                    Frame::String(channel.clone()),
+                    Frame::String(message.clone()),
+                ]);
+                if subscriber.push(frame) {
+                    receivers += 1;
+                }
+            }
+        }
+        receivers
+    }
+}
+
+impl KeyspaceEvent {
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            KeyspaceEvent::Set => b"set",
+            KeyspaceEvent::Del => b"del",
+            KeyspaceEvent::Expired => b"expired",
+            KeyspaceEvent::Evicted => b"evicted",
+        }
+    }
+}
+
+impl KeyspaceEvents {
+    pub fn new(keyspace: Bytes, tracker: Arc<Tracker>) -> Self {
+        KeyspaceEvents {
+            keyspace: RwLock::new(keyspace),
+            tracker,
+            watchers: RwLock::new(HashMap::new()),
+            watching: AtomicUsize::new(0),
+        }
+    }
+
+    /// Pushes events for keys matching the pattern to the subscriber, watching
+    /// again replaces the previous pattern.
+    pub fn watch(&self, subscriber: &Subscriber, pattern: Option<Bytes>) {
+        let mut handle = self.watchers.write();
+        handle.insert(subscriber.id(), (subscriber.clone(), pattern));
+        self.watching.store(handle.len(), Ordering::Relaxed);
+    }
+
+    pub fn unwatch(&self, subscriber: &Subscriber) -> bool {
+        let mut handle = self.watchers.write();
+        let removed = handle.remove(&subscriber.id()).is_some();
+        self.watching.store(handle.len(), Ordering::Relaxed);
+        removed
+    }
+
+    /// Changes the keyspace reported in events, watchers keep watching the keyspace
+    /// under its new name.
+    pub fn rename(&self, keyspace: Bytes) {
+        *self.keyspace.write() = keyspace;
+    }
+
+    /// Tells the clients caching the key that it has changed.
+    pub fn invalidate(&self, key: &Bytes) {
+        self.tracker.invalidate(&self.keyspace.read(), key);
+    }
+
+    pub fn emit(&self, event: KeyspaceEvent, key: &Bytes) {
+        if self.watching.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let keyspace = self.keyspace.read().clone();
+        for (subscriber, pattern) in self.watchers.read().values() {
+            if let Some(pattern) = pattern {
+                if !pattern::matches(pattern, key) {
+                    continue;
+                }
+            }
+            subscriber.push(Frame::Map(vec![
+                Frame::String(Bytes::from_static(b"event")),
+                Frame::String(Bytes::from_static(event.as_bytes())),
+                Frame::String(Bytes::from_static(b"keyspace")),
+                Frame::String(keyspace.clone()),
+                Frame::String(Bytes::from_static(b"key")),
+                Frame::String(key.clone()),
+            ]));
+        }
+    }
+}
+
+fn remove(handle: &mut HashMap<Bytes, HashMap<u64, Subscriber>>, name: &Bytes, id: u64) {
+    if let Some(subscribers) = handle.get_mut(name) {
+        subscribers.remove(&id);
+        if subscribers.is_empty() {
+            handle.remove(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_given_channel_and_pattern_subscribers_pushes_to_both() {
+        let pubsub = PubSub::new();
+        let (channel_subscriber, mut channel_rx) = Subscriber::new(8);
+        let (pattern_subscriber, mut pattern_rx) = Subscriber::new(8);
+        pubsub.subscribe(Bytes::from("news.sports"), &channel_subscriber);
+        pubsub.psubscribe(Bytes::from("news.*"), &pattern_subscriber);
+
+        let receivers = pubsub.publish(&Bytes::from("news.sports"), &Bytes::from("goal"));
+
+        assert_eq!(receivers, 2);
+        assert_eq!(
+            channel_rx.try_recv().unwrap(),
+            Frame::Array(vec![
+                Frame::String(Bytes::from("message")),
+                Frame::String(Bytes::from("news.sports")),
+                Frame::String(Bytes::from("goal")),
+            ])
+        );
+        assert_eq!(
+            pattern_rx.try_recv().unwrap(),
+            Frame::Array(vec![
+                Frame::String(Bytes::from("pmessage")),
+                Frame::String(Bytes::from("news.*")),
+                Frame::String(Bytes::from("news.sports")),
+                Frame::String(Bytes::from("goal")),
+            ])
+        );
+    }
+
+    #[test]
+    fn publish_given_unsubscribed_channel_returns_zero() {
+        let pubsub = PubSub::new();
+        let (subscriber, _rx) = Subscriber::new(8);
+        pubsub.subscribe(Bytes::from("foo"), &subscriber);
+        pubsub.unsubscribe(&Bytes::from("foo"), &subscriber);
+        assert_eq!(pubsub.publish(&Bytes::from("foo"), &Bytes::from("bar")), 0);
+    }
+
+    #[test]
+    fn emit_given_watcher_with_pattern_pushes_matching_keys_only() {
+        let events = KeyspaceEvents::new(Bytes::from("users"), Arc::new(Tracker::new()));
+        let (subscriber, mut rx) = Subscriber::new(8);
+        events.watch(&subscriber, Some(Bytes::from("session:*")));
+
+        events.emit(KeyspaceEvent::Set, &Bytes::from("profile:1"));
+        events.emit(KeyspaceEvent::Expired, &Bytes::from("session:1"));
+
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            Frame::Map(vec![
+                Frame::String(Bytes::from("event")),
+                Frame::String(Bytes::from("expired")),
+                Frame::String(Bytes::from("keyspace")),
+                Frame::String(Bytes::from("users")),
+                Frame::String(Bytes::from("key")),
+                Frame::String(Bytes::from("session:1")),
+            ])
+        );
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn emit_given_unwatched_keyspace_pushes_nothing() {
+        let events = KeyspaceEvents::new(Bytes::from("users"), Arc::new(Tracker::new()));
+        let (subscriber, mut rx) = Subscriber::new(8);
+        events.watch(&subscriber, None);
+        assert!(events.unwatch(&subscriber));
+
+        events.emit(KeyspaceEvent::Del, &Bytes::from("foo"));
+
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn publish_given_full_buffer_marks_subscriber_overflowed() {
+        let pubsub = PubSub::new();
+        let (subscriber, _rx) = Subscriber::new(1);
+        pubsub.subscribe(Bytes::from("foo"), &subscriber);
+
+        assert_eq!(pubsub.publish(&Bytes::from("foo"), &Bytes::from("1")), 1);
+        assert!(!subscriber.overflowed());
+        assert_eq!(pubsub.publish(&Bytes::from("foo"), &Bytes::from("2")), 0);
+        assert!(subscriber.overflowed());
+    }
+}
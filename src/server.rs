@@ -1,46 +1,100 @@
-use crate::command;
+use crate::acl::{Acl, User};
+use crate::clients::{Client, Clients};
+use crate::command::{self, ClientKill, Command};
 use crate::config::ServerConfig;
 use crate::connection::Connection;
-use crate::db::Db;
+use crate::db::{Db, ExecuteCommandError};
+use crate::frame::Frame;
+use crate::metrics;
+use crate::pubsub::Subscriber;
+use crate::slowlog::SlowLog;
+use crate::tls;
+use crate::tracking::TrackingMode;
 use anyhow::Result;
+use bytes::Bytes;
 use crossbeam::sync::WaitGroup;
+use socket2::{SockRef, TcpKeepalive};
+use std::collections::HashSet;
+use std::fs::{self, Permissions};
+use std::io;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use sysinfo::{Pid, ProcessExt, System, SystemExt};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
 use tokio::signal;
-use tokio::sync::broadcast;
-use tracing::{debug, error, info};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, error, info, warn};
+
+// a client that does not complete the tls handshake in time is disconnected
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
 struct Server {
-    ln: TcpListener,
-    cfg: ServerConfig,
+    ln: Option<TcpListener>,
+    cfg: Arc<ServerConfig>,
+    clients: Arc<AtomicUsize>,
     wg: WaitGroup,
     db: Arc<Db>,
     done_tx: broadcast::Sender<()>,
     evict_tx: broadcast::Sender<()>,
 }
 
-struct ConnectionHandler {
-    connection: Connection<TcpStream>,
+struct ConnectionHandler<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    connection: Connection<T>,
     done: broadcast::Receiver<()>,
     db: Arc<Db>,
+    transaction: Option<Transaction>,
+    subscriber: Subscriber,
+    pushes: mpsc::Receiver<Frame>,
+    channels: HashSet<Bytes>,
+    patterns: HashSet<Bytes>,
+    watching: HashSet<Bytes>,
+    tracking: Option<TrackingMode>,
+    monitoring: bool,
+    acl: Arc<Acl>,
+    user: Option<Arc<User>>,
+    idle_timeout: Duration,
+    last_active: Instant,
+    client: Arc<Client>,
+}
+
+// commands queued between MULTI and EXEC, a transaction is aborted if any
+// command fails to queue and is then discarded on EXEC
+#[derive(Default)]
+struct Transaction {
+    commands: Vec<Command>,
+    aborted: bool,
 }
 
-pub async fn start(ln: TcpListener, cfg: ServerConfig) -> Result<()> {
+/// Starts the server, `ln` is None when plain tcp connections are disabled.
+pub async fn start(ln: Option<TcpListener>, cfg: ServerConfig) -> Result<()> {
     let srv = Server::new(ln, cfg);
     srv.start().await
 }
 
 impl Server {
-    pub fn new(ln: TcpListener, cfg: ServerConfig) -> Self {
+    pub fn new(ln: Option<TcpListener>, cfg: ServerConfig) -> Self {
         let wg = WaitGroup::new();
         let (done_tx, _) = broadcast::channel(1);
         let (evict_tx, _) = broadcast::channel(1);
-        let db = Db::new(done_tx.subscribe(), wg.clone(), evict_tx.subscribe());
+        let db = Db::new(
+            done_tx.subscribe(),
+            wg.clone(),
+            evict_tx.subscribe(),
+            cfg.max_memory(),
+            SlowLog::new(cfg.slowlog_threshold(), cfg.slowlog_max_len()),
+        );
         Server {
             ln,
-            cfg,
+            cfg: Arc::new(cfg),
+            clients: Arc::new(AtomicUsize::new(0)),
             wg,
             done_tx,
             db: Arc::new(db),
@@ -49,15 +103,18 @@ impl Server {
     }
 
     pub async fn start(self) -> Result<()> {
-        info!(
-            "server started on port {}:{}",
-            self.cfg.bind(),
-            self.cfg.port()
-        );
+        if self.ln.is_some() {
+            info!(
+                "server started on port {}:{}",
+                self.cfg.bind(),
+                self.cfg.port()
+            );
+        }
         let monitor_wg = self.wg.clone();
         let mut monitor_done_rx = self.done_tx.subscribe();
         let monitor_evict_tx = self.evict_tx.clone();
         let server_max_memory = self.cfg.max_memory();
+        let monitor_db = self.db.clone();
         // FIXME: move this to a separate fn
         tokio::spawn(async move {
             let pid = std::process::id() as i32;
@@ -73,6 +130,7 @@ impl Server {
                         monitor.refresh_process(Pid::from(pid));
                         if let Some(process) = monitor.process(Pid::from(pid)) {
                             let memory = process.memory();
+                            monitor_db.stats().sample(memory);
                             if memory >= server_max_memory && server_max_memory > 0 {
                                 debug!("broadcasting evict event, server max memory (bytes) = {}, current memory usage (bytes) = {}", server_max_memory, memory);
                                 if let Err(err) = monitor_evict_tx.send(()) {
@@ -88,15 +146,81 @@ impl Server {
             }
             drop(monitor_wg)
         });
+        let tls_ln = match self.cfg.tls_port() {
+            Some(tls_port) => {
+                let acceptor = tls::acceptor(&self.cfg)?;
+                let ln = TcpListener::bind(format!("{}:{}", self.cfg.bind(), tls_port)).await?;
+                info!(
+                    "accepting tls connections on port {}:{}",
+                    self.cfg.bind(),
+                    tls_port
+                );
+                Some((ln, acceptor))
+            }
+            None => None,
+        };
+        if let Some(metrics_port) = self.cfg.metrics_port() {
+            let ln = TcpListener::bind(format!("{}:{}", self.cfg.bind(), metrics_port)).await?;
+            info!(
+                "serving metrics on http://{}:{}/metrics",
+                self.cfg.bind(),
+                metrics_port
+            );
+            tokio::spawn(metrics::serve(
+                ln,
+                self.db.clone(),
+                self.done_tx.subscribe(),
+                self.wg.clone(),
+            ));
+        }
+        let unix_ln = match self.cfg.unix_socket() {
+            Some(path) => {
+                let ln = bind_unix_socket(path, self.cfg.unix_socket_perm())?;
+                info!("accepting connections on unix socket {}", path);
+                Some(ln)
+            }
+            None => None,
+        };
         loop {
             tokio::select! {
-                maybe_connection = self.ln.accept() => {
-                    let (stream, _) = maybe_connection?;
-                    let mut handler = ConnectionHandler::new(self.done_tx.subscribe(), stream, self.cfg.connection_buffer_size(), self.db.clone());
+                maybe_connection = accept_tcp(&self.ln) => {
+                    let (stream, addr) = maybe_connection?;
+                    self.set_keepalive(&stream);
+                    self.spawn_handler(stream, addr);
+                }
+                maybe_connection = accept_unix(&unix_ln) => {
+                    let stream = maybe_connection?;
+                    self.spawn_handler(stream, "unix".to_string());
+                }
+                maybe_connection = accept_tls(&tls_ln) => {
+                    let (stream, addr, acceptor) = maybe_connection?;
+                    self.set_keepalive(&stream);
+                    let done = self.done_tx.subscribe();
+                    let cfg = self.cfg.clone();
+                    let db = self.db.clone();
+                    let clients = self.clients.clone();
                     let wg = self.wg.clone();
+                    // the handshake runs in the connection task so that a slow client
+                    // can not hold up the accept loop, the connection is counted before
+                    // the handshake so that pending handshakes are limited by max clients
                     tokio::spawn(async move {
-                        if let Err(e) = handler.handle().await {
-                            error!("{}", e)
+                        // a rejected client is disconnected without a handshake, so
+                        // it gets no error
+                        if admit(&clients, &cfg, &db) {
+                            let handshake = acceptor.accept(stream);
+                            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, handshake).await {
+                                Ok(Ok(stream)) => {
+                                    handle(stream, addr, done, cfg, db, &clients).await
+                                }
+                                Ok(Err(e)) => {
+                                    debug!("tls handshake failed, error = {}", e);
+                                    clients.fetch_sub(1, Ordering::Relaxed);
+                                }
+                                Err(_) => {
+                                    debug!("tls handshake timed out");
+                                    clients.fetch_sub(1, Ordering::Relaxed);
+                                }
+                            }
                         }
                         drop(wg);
                     });
@@ -104,11 +228,17 @@ impl Server {
                  _ = signal::ctrl_c() => {
                     info!("shutdown signal received");
                     drop(self.ln);
+                    drop(unix_ln);
                     drop(self.done_tx);
                     break;
                  }
             }
         }
+        if let Some(path) = self.cfg.unix_socket() {
+            if let Err(e) = fs::remove_file(path) {
+                warn!("failed to remove unix socket {}, error = {}", path, e);
+            }
+        }
         drop(self.db);
         self.wg.wait();
         info!("shutdown complete, bye bye :)");
@@ -116,39 +246,251 @@ impl Server {
     }
 }
 
-impl ConnectionHandler {
+impl Server {
+    fn spawn_handler<T>(&self, stream: T, addr: String)
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let done = self.done_tx.subscribe();
+        let cfg = self.cfg.clone();
+        let db = self.db.clone();
+        let clients = self.clients.clone();
+        let wg = self.wg.clone();
+        tokio::spawn(async move {
+            serve(stream, addr, done, cfg, db, clients).await;
+            drop(wg);
+        });
+    }
+
+    fn set_keepalive(&self, stream: &TcpStream) {
+        let keepalive = self.cfg.tcp_keepalive();
+        if keepalive.is_zero() {
+            return;
+        }
+        let params = TcpKeepalive::new()
+            .with_time(keepalive)
+            .with_interval(keepalive);
+        if let Err(e) = SockRef::from(stream).set_tcp_keepalive(&params) {
+            warn!("failed to enable tcp keepalive, error = {}", e);
+        }
+    }
+}
+
+// runs the connection until it is closed, connections beyond the max number of clients
+// are sent an error and closed right away
+async fn serve<T>(
+    stream: T,
+    addr: String,
+    done: broadcast::Receiver<()>,
+    cfg: Arc<ServerConfig>,
+    db: Arc<Db>,
+    clients: Arc<AtomicUsize>,
+) where
+    T: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    if !admit(&clients, &cfg, &db) {
+        let mut connection = Connection::new(stream, cfg.connection_buffer_size());
+        if let Err(e) = connection
+            .write_error(ExecuteCommandError::MaxClientsReached)
+            .await
+        {
+            debug!("failed to write to rejected connection, error = {}", e);
+        }
+        return;
+    }
+    handle(stream, addr, done, cfg, db, &clients).await;
+}
+
+// counts the connection against max clients, a rejected connection is not counted
+fn admit(clients: &AtomicUsize, cfg: &ServerConfig, db: &Db) -> bool {
+    let connected = clients.fetch_add(1, Ordering::Relaxed) + 1;
+    if cfg.max_clients() > 0 && connected > cfg.max_clients() {
+        clients.fetch_sub(1, Ordering::Relaxed);
+        db.stats().record_rejected_connection();
+        warn!("rejecting connection, max number of clients reached");
+        return false;
+    }
+    true
+}
+
+// handles the commands of an admitted connection until it closes
+async fn handle<T>(
+    stream: T,
+    addr: String,
+    done: broadcast::Receiver<()>,
+    cfg: Arc<ServerConfig>,
+    db: Arc<Db>,
+    clients: &AtomicUsize,
+) where
+    T: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    db.stats().record_connection();
+    let mut handler = ConnectionHandler::new(done, stream, addr, &cfg, db);
+    if let Err(e) = handler.handle().await {
+        error!("{}", e)
+    }
+    drop(handler);
+    clients.fetch_sub(1, Ordering::Relaxed);
+}
+
+// binds the unix socket, removing the file left behind by a previous run that did not
+// shut down cleanly
+fn bind_unix_socket(path: &str, perm: u32) -> Result<UnixListener> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => anyhow::bail!("unix socket path {} exists and is not a socket", path),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let ln = UnixListener::bind(path)?;
+    fs::set_permissions(path, Permissions::from_mode(perm))?;
+    Ok(ln)
+}
+
+// holds off commands while CLIENT PAUSE is in effect, CLIENT commands are still run
+// so that the server can be inspected during the pause
+async fn wait_while_paused(clients: &Clients) {
+    while let Some(until) = clients.paused_until() {
+        tokio::time::sleep_until(until).await;
+    }
+}
+
+// completes once the deadline passes, never completes without one
+async fn idle(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+// waits for a connection on the tcp listener, never completes when tcp is disabled
+async fn accept_tcp(ln: &Option<TcpListener>) -> io::Result<(TcpStream, String)> {
+    match ln {
+        Some(ln) => {
+            let (stream, addr) = ln.accept().await?;
+            Ok((stream, addr.to_string()))
+        }
+        None => std::future::pending().await,
+    }
+}
+
+// waits for a connection on the unix socket, never completes when it is not configured
+async fn accept_unix(ln: &Option<UnixListener>) -> io::Result<UnixStream> {
+    match ln {
+        Some(ln) => ln.accept().await.map(|(stream, _)| stream),
+        None => std::future::pending().await,
+    }
+}
+
+// waits for a connection on the tls listener, never completes when tls is disabled
+async fn accept_tls(
+    ln: &Option<(TcpListener, TlsAcceptor)>,
+) -> io::Result<(TcpStream, String, TlsAcceptor)> {
+    match ln {
+        Some((ln, acceptor)) => {
+            let (stream, addr) = ln.accept().await?;
+            Ok((stream, addr.to_string(), acceptor.clone()))
+        }
+        None => std::future::pending().await,
+    }
+}
+
+impl<T> ConnectionHandler<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send,
+{
     pub fn new(
         done: broadcast::Receiver<()>,
-        stream: TcpStream,
-        connection_buf_size: usize,
+        stream: T,
+        addr: String,
+        cfg: &ServerConfig,
         db: Arc<Db>,
     ) -> Self {
-        let connection = Connection::new(stream, connection_buf_size);
+        let connection = Connection::new(stream, cfg.connection_buffer_size());
+        let (subscriber, pushes) = Subscriber::new(cfg.subscriber_buffer_size());
+        let client = db.clients().register(subscriber.clone(), addr);
         ConnectionHandler {
             connection,
             done,
             db,
+            transaction: None,
+            subscriber,
+            pushes,
+            channels: HashSet::new(),
+            patterns: HashSet::new(),
+            watching: HashSet::new(),
+            tracking: None,
+            monitoring: false,
+            acl: cfg.acl(),
+            user: None,
+            idle_timeout: cfg.idle_timeout(),
+            last_active: Instant::now(),
+            client,
         }
     }
 
     pub async fn handle(&mut self) -> Result<()> {
         debug!("new connection started");
         loop {
+            let idle_deadline = self.idle_deadline();
             let maybe_frame = tokio::select! {
                 _ = self.done.recv() => {
                     break;
                 }
+                _ = idle(idle_deadline) => {
+                    debug!("closing idle connection");
+                    break;
+                }
+                _ = self.client.killed() => {
+                    debug!("closing connection killed by another client");
+                    break;
+                }
                 res = self.connection.read_frame() => res?,
+                Some(frame) = self.pushes.recv() => {
+                    // pending messages are dropped with the connection, a client that
+                    // fell behind has to resubscribe and resync anyway
+                    if self.subscriber.overflowed() {
+                        warn!("disconnecting subscriber, output buffer limit reached");
+                        break;
+                    }
+                    tokio::select! {
+                        res = self.connection.write_frame(&frame) => res?,
+                        _ = self.subscriber.wait_for_overflow() => {
+                            warn!("disconnecting subscriber, output buffer limit reached");
+                            break;
+                        }
+                    }
+                    continue;
+                }
             };
 
             let frame = match maybe_frame {
                 Some(frame) => frame,
                 None => return Ok(()),
             };
+            self.last_active = Instant::now();
+
+            // the arguments are kept for the slow log and monitors as parsing consumes
+            // the frame
+            let keep_args = self.db.slowlog().is_enabled() || self.db.monitors().is_active();
+            let args = match &frame {
+                Frame::Array(frames) if keep_args => frames
+                    .iter()
+                    .skip(1)
+                    .map(|frame| match frame {
+                        Frame::String(arg) => arg.clone(),
+                        _ => Bytes::new(),
+                    })
+                    .collect(),
+                _ => vec![],
+            };
 
             let maybe_cmd = match command::parse(frame) {
                 Ok(cmd) => Some(cmd),
                 Err(e) => {
+                    if let Some(transaction) = self.transaction.as_mut() {
+                        transaction.aborted = true;
+                    }
                     self.connection.write_error(e).await?;
                     None
                 }
@@ -158,8 +500,71 @@ impl ConnectionHandler {
                 Some(cmd) => cmd,
                 None => continue,
             };
+            let name = cmd.name();
+            self.client
+                .record_command(name, self.connection.buffer_capacity());
+            if let Err(e) = self.authorize(&cmd) {
+                if let Some(transaction) = self.transaction.as_mut() {
+                    transaction.aborted = true;
+                }
+                self.connection.write_error(e).await?;
+                continue;
+            }
+
+            // monitors only see commands the connection was allowed to run, and
+            // passwords are never sent to them
+            let monitored_args = match cmd {
+                Command::Auth(_) => &[],
+                _ => args.as_slice(),
+            };
+            self.db
+                .monitors()
+                .feed(self.client.addr(), cmd.keyspace(), name, monitored_args);
 
-            let maybe_result = match self.db.execute(cmd).await {
+            let started = Instant::now();
+            let result = match cmd {
+                Command::Multi => self.begin(),
+                Command::Exec => self.commit().await,
+                Command::Discard => self.rollback(),
+                cmd if self.transaction.is_some() => self.queue(cmd),
+                Command::Auth(cmd) => self.auth(cmd.username(), cmd.password()),
+                Command::ClientList => Ok(self.client_list()),
+                Command::ClientInfo => Ok(self.client.to_frame()),
+                Command::ClientKill(cmd) => self.client_kill(&cmd),
+                Command::ClientSetName(cmd) => {
+                    self.client.set_name(cmd.name());
+                    Ok(Frame::Boolean(true))
+                }
+                Command::ClientPause(cmd) => {
+                    self.db.clients().pause(cmd.duration());
+                    Ok(Frame::Boolean(true))
+                }
+                Command::Subscribe(cmd) => self.subscribe(cmd.channels()),
+                Command::PSubscribe(cmd) => self.psubscribe(cmd.channels()),
+                Command::Unsubscribe(cmd) => self.unsubscribe(cmd.channels()),
+                Command::PUnsubscribe(cmd) => self.punsubscribe(cmd.channels()),
+                Command::WatchEvents(cmd) => self.watch_events(cmd.keyspace(), cmd.pattern()),
+                Command::UnwatchEvents(cmd) => self.unwatch_events(cmd.keyspace()),
+                Command::Tracking(cmd) => self.tracking(cmd.mode()),
+                Command::Monitor => {
+                    self.db.monitors().add(&self.subscriber);
+                    self.monitoring = true;
+                    Ok(Frame::Boolean(true))
+                }
+                cmd => {
+                    wait_while_paused(self.db.clients()).await;
+                    self.track_reads(&cmd);
+                    let started = Instant::now();
+                    let result = self.db.execute(cmd).await;
+                    self.db
+                        .slowlog()
+                        .record(name, &args, self.client.addr(), started.elapsed());
+                    result
+                }
+            };
+            self.db.stats().record_command(name, started.elapsed());
+
+            let maybe_result = match result {
                 Ok(frame) => Some(frame),
                 Err(e) => {
                     self.connection.write_error(e).await?;
@@ -175,4 +580,385 @@ impl ConnectionHandler {
         }
         Ok(())
     }
+
+    // once users are configured a connection has to authenticate before it can run
+    // anything but AUTH and PING, and then only what the rules of its user allow
+    fn authorize(&self, cmd: &Command) -> Result<(), ExecuteCommandError> {
+        if !self.acl.is_enabled() || matches!(cmd, Command::Auth(_) | Command::Ping) {
+            return Ok(());
+        }
+        match &self.user {
+            Some(user) if user.is_allowed(cmd) => Ok(()),
+            Some(user) => Err(ExecuteCommandError::PermissionDenied(
+                user.name().to_string(),
+                cmd.name().to_string(),
+            )),
+            None => Err(ExecuteCommandError::AuthRequired),
+        }
+    }
+
+    fn auth(&mut self, username: &str, password: &[u8]) -> Result<Frame, ExecuteCommandError> {
+        if !self.acl.is_enabled() {
+            return Err(ExecuteCommandError::AuthNotEnabled);
+        }
+        let user = self
+            .acl
+            .authenticate(username, password)
+            .ok_or(ExecuteCommandError::InvalidCredentials)?;
+        self.user = Some(user);
+        Ok(Frame::Boolean(true))
+    }
+
+    fn begin(&mut self) -> Result<Frame, ExecuteCommandError> {
+        if self.transaction.is_some() {
+            return Err(ExecuteCommandError::NestedTransaction);
+        }
+        self.transaction = Some(Transaction::default());
+        Ok(Frame::Boolean(true))
+    }
+
+    fn queue(&mut self, cmd: Command) -> Result<Frame, ExecuteCommandError> {
+        let transaction = self
+            .transaction
+            .as_mut()
+            .ok_or(ExecuteCommandError::NoTransaction)?;
+        // keyspaces can not be created, altered, renamed, copied or dropped while the
+        // transaction holds them and subscriptions belong to the connection rather than
+        // the db
+        if let Command::Create(_)
+        | Command::Alter(_)
+        | Command::RenameKeyspace(_)
+        | Command::CopyKeyspace(_)
+        | Command::Drop(_)
+        | Command::Subscribe(_)
+        | Command::PSubscribe(_)
+        | Command::Unsubscribe(_)
+        | Command::PUnsubscribe(_)
+        | Command::WatchEvents(_)
+        | Command::UnwatchEvents(_)
+        | Command::Tracking(_)
+        | Command::Auth(_)
+        | Command::ClientList
+        | Command::ClientInfo
+        | Command::ClientKill(_)
+        | Command::ClientSetName(_)
+        | Command::ClientPause(_)
+        | Command::Monitor = cmd
+        {
+            transaction.aborted = true;
+            return Err(ExecuteCommandError::NotAllowedInTransaction(
+                cmd.name().to_string(),
+            ));
+        }
+        transaction.commands.push(cmd);
+        Ok(Frame::String(Bytes::from_static(b"QUEUED")))
+    }
+
+    async fn commit(&mut self) -> Result<Frame, ExecuteCommandError> {
+        let transaction = self
+            .transaction
+            .take()
+            .ok_or(ExecuteCommandError::NoTransaction)?;
+        if transaction.aborted {
+            return Err(ExecuteCommandError::TransactionAborted);
+        }
+        wait_while_paused(self.db.clients()).await;
+        for cmd in &transaction.commands {
+            self.track_reads(cmd);
+        }
+        let started = Instant::now();
+        let result = self.db.execute_transaction(transaction.commands);
+        self.db
+            .slowlog()
+            .record("exec", &[], self.client.addr(), started.elapsed());
+        result
+    }
+
+    fn rollback(&mut self) -> Result<Frame, ExecuteCommandError> {
+        self.transaction
+            .take()
+            .ok_or(ExecuteCommandError::NoTransaction)?;
+        Ok(Frame::Boolean(true))
+    }
+
+    fn subscribe(&mut self, channels: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        for channel in channels {
+            if self.channels.insert(channel.clone()) {
+                self.db
+                    .pubsub()
+                    .subscribe(channel.clone(), &self.subscriber);
+            }
+        }
+        Ok(self.subscriptions())
+    }
+
+    fn psubscribe(&mut self, patterns: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        for pattern in patterns {
+            if self.patterns.insert(pattern.clone()) {
+                self.db
+                    .pubsub()
+                    .psubscribe(pattern.clone(), &self.subscriber);
+            }
+        }
+        Ok(self.subscriptions())
+    }
+
+    fn unsubscribe(&mut self, channels: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        let channels: Vec<Bytes> = if channels.is_empty() {
+            self.channels.drain().collect()
+        } else {
+            channels
+                .iter()
+                .filter(|channel| self.channels.remove(*channel))
+                .cloned()
+                .collect()
+        };
+        for channel in &channels {
+            self.db.pubsub().unsubscribe(channel, &self.subscriber);
+        }
+        Ok(self.subscriptions())
+    }
+
+    fn punsubscribe(&mut self, patterns: &[Bytes]) -> Result<Frame, ExecuteCommandError> {
+        let patterns: Vec<Bytes> = if patterns.is_empty() {
+            self.patterns.drain().collect()
+        } else {
+            patterns
+                .iter()
+                .filter(|pattern| self.patterns.remove(*pattern))
+                .cloned()
+                .collect()
+        };
+        for pattern in &patterns {
+            self.db.pubsub().punsubscribe(pattern, &self.subscriber);
+        }
+        Ok(self.subscriptions())
+    }
+
+    fn watch_events(
+        &mut self,
+        keyspace: Bytes,
+        pattern: Option<Bytes>,
+    ) -> Result<Frame, ExecuteCommandError> {
+        let frame = self.db.watch_events(&keyspace, pattern, &self.subscriber)?;
+        self.watching.insert(keyspace);
+        Ok(frame)
+    }
+
+    fn unwatch_events(&mut self, keyspace: Bytes) -> Result<Frame, ExecuteCommandError> {
+        self.watching.remove(&keyspace);
+        let removed = self.db.unwatch_events(&keyspace, &self.subscriber);
+        Ok(Frame::Boolean(removed))
+    }
+
+    fn tracking(&mut self, mode: Option<&TrackingMode>) -> Result<Frame, ExecuteCommandError> {
+        match mode {
+            Some(mode) => self.db.tracker().enable(&self.subscriber, mode),
+            None => self.db.tracker().disable(&self.subscriber),
+        }
+        self.tracking = mode.cloned();
+        Ok(Frame::Boolean(true))
+    }
+
+    // remembers the keys a command reads so the client can be told when they change,
+    // keys are tracked before they are read so that no write in between is missed
+    fn track_reads(&self, cmd: &Command) {
+        if self.tracking != Some(TrackingMode::Default) {
+            return;
+        }
+        let tracker = self.db.tracker();
+        match cmd {
+            Command::Get(cmd) => tracker.track(&cmd.keyspace(), &cmd.key(), &self.subscriber),
+            Command::MGet(cmd) => {
+                for key in cmd.keys() {
+                    tracker.track(&cmd.keyspace(), key, &self.subscriber);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn client_list(&self) -> Frame {
+        let clients = self.db.clients().list();
+        Frame::Array(clients.iter().map(|client| client.to_frame()).collect())
+    }
+
+    fn client_kill(&self, target: &ClientKill) -> Result<Frame, ExecuteCommandError> {
+        let killed = match target {
+            ClientKill::Id(id) => self.db.clients().kill_by_id(*id),
+            ClientKill::Addr(addr) => self.db.clients().kill_by_addr(addr),
+        };
+        if !killed {
+            return Err(ExecuteCommandError::NoSuchClient);
+        }
+        Ok(Frame::Boolean(true))
+    }
+
+    // connections waiting for pushes are not idle even if they send nothing
+    fn idle_deadline(&self) -> Option<Instant> {
+        if self.idle_timeout.is_zero()
+            || !self.channels.is_empty()
+            || !self.patterns.is_empty()
+            || !self.watching.is_empty()
+            || self.monitoring
+            || self.tracking.is_some()
+        {
+            return None;
+        }
+        Some(self.last_active + self.idle_timeout)
+    }
+
+    // number of channels and patterns the connection is subscribed to
+    fn subscriptions(&self) -> Frame {
+        Frame::Integer((self.channels.len() + self.patterns.len()) as i64)
+    }
+}
+
+impl<T> Drop for ConnectionHandler<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    fn drop(&mut self) {
+        for channel in &self.channels {
+            self.db.pubsub().unsubscribe(channel, &self.subscriber);
+        }
+        for pattern in &self.patterns {
+            self.db.pubsub().punsubscribe(pattern, &self.subscriber);
+        }
+        for keyspace in &self.watching {
+            self.db.unwatch_events(keyspace, &self.subscriber);
+        }
+        if self.tracking.is_some() {
+            self.db.tracker().disable(&self.subscriber);
+        }
+        if self.monitoring {
+            self.db.monitors().remove(&self.subscriber);
+        }
+        self.db.clients().unregister(self.client.id());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{self, TestDb};
+    use tokio::io::DuplexStream;
+
+    // loads a config file written to a directory unique to the test
+    fn server_config(test: &str, contents: &str) -> ServerConfig {
+        let dir =
+            std::env::temp_dir().join(format!("segment-server-{}-{}", std::process::id(), test));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("segment.conf");
+        fs::write(&path, contents).unwrap();
+        ServerConfig::load_from_disk(&path.to_string_lossy()).unwrap()
+    }
+
+    // starts a connection handler and returns the client side of the connection
+    fn connect(cfg: &ServerConfig, db: &TestDb, addr: &str) -> Connection<DuplexStream> {
+        let (client, server) = tokio::io::duplex(4096);
+        let mut handler =
+            ConnectionHandler::new(db.done(), server, addr.to_string(), cfg, db.arc());
+        tokio::spawn(async move { handler.handle().await });
+        Connection::new(client, 4096)
+    }
+
+    async fn send(connection: &mut Connection<DuplexStream>, args: &[&str]) -> Frame {
+        connection
+            .write_frame(&test_util::frame(args))
+            .await
+            .unwrap();
+        connection.read_frame().await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn exec_given_queueing_error_aborts_transaction() {
+        let db = TestDb::new();
+        let cfg = server_config("queueing-error", "");
+        let mut connection = connect(&cfg, &db, "127.0.0.1:1");
+        send(&mut connection, &["create", "a"]).await;
+
+        send(&mut connection, &["multi"]).await;
+        assert_eq!(
+            send(&mut connection, &["set", "a", "key", "value"]).await,
+            Frame::String(Bytes::from("QUEUED"))
+        );
+        assert!(matches!(
+            send(&mut connection, &["create", "b"]).await,
+            Frame::Error(_)
+        ));
+        assert!(matches!(
+            send(&mut connection, &["set", "a"]).await,
+            Frame::Error(_)
+        ));
+        assert_eq!(
+            send(&mut connection, &["exec"]).await,
+            Frame::Error(Bytes::from(
+                ExecuteCommandError::TransactionAborted.to_string()
+            ))
+        );
+
+        let get = test_util::cmd(&["get", "a", "key"]);
+        assert_eq!(db.execute(get).await.unwrap(), Frame::Null);
+    }
+
+    #[tokio::test]
+    async fn monitor_given_acl_feeds_only_authorized_commands() {
+        let db = TestDb::new();
+        // sha256 of "admin" and "reader"
+        let cfg = server_config(
+            "monitor-acl",
+            "user=admin 8c6976e5b5410415bde908bd4dee15dfb167a9c873fc4bb8a81f6f2ab448a918 +@all ~*\n\
+             user=reader 3d0941964aa3ebdcb00ccef58b1bb399f9f898465e9886d5aec7f31090a0fb30 +@all ~cache:*\n",
+        );
+        let mut monitor = connect(&cfg, &db, "127.0.0.1:1");
+        send(&mut monitor, &["auth", "admin", "admin"]).await;
+        assert_eq!(send(&mut monitor, &["monitor"]).await, Frame::Boolean(true));
+
+        let mut client = connect(&cfg, &db, "127.0.0.1:2");
+        assert!(matches!(
+            send(&mut client, &["get", "users", "key"]).await,
+            Frame::Error(_)
+        ));
+        send(&mut client, &["auth", "reader", "reader"]).await;
+        assert!(matches!(
+            send(&mut client, &["get", "users", "key"]).await,
+            Frame::Error(_)
+        ));
+        assert!(matches!(
+            send(&mut client, &["monitor"]).await,
+            Frame::Error(_)
+        ));
+        send(&mut client, &["ping"]).await;
+
+        // the denied commands are skipped and the password is left out
+        for (command, args) in [("auth", vec![]), ("ping", vec![])] {
+            let Some(Frame::Map(fields)) = monitor.read_frame().await.unwrap() else {
+                panic!("expected a map");
+            };
+            assert_eq!(fields[7], Frame::String(Bytes::from(command)));
+            assert_eq!(fields[9], Frame::Array(args));
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_deadline_given_tracking_keeps_connection_open() {
+        let db = TestDb::new();
+        let cfg = server_config("idle-tracking", "idle_timeout=1\n");
+        let mut tracking = connect(&cfg, &db, "127.0.0.1:1");
+        assert_eq!(
+            send(&mut tracking, &["tracking", "on"]).await,
+            Frame::Boolean(true)
+        );
+        let mut idle = connect(&cfg, &db, "127.0.0.1:2");
+        send(&mut idle, &["ping"]).await;
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        assert_eq!(idle.read_frame().await.unwrap(), None);
+        assert_eq!(
+            send(&mut tracking, &["ping"]).await,
+            Frame::String(Bytes::from("PONG"))
+        );
+    }
 }
@@ -0,0 +1,189 @@
+use crate::frame::Frame;
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// arguments are truncated so that a slow command with a large value can not blow up
+// the memory held by the log
+const MAX_ARGS: usize = 32;
+const MAX_ARG_LEN: usize = 128;
+
+/// Bounded log of the commands that took longer than the threshold to execute, once
+/// full the oldest entry is dropped for every new one.
+#[derive(Debug)]
+pub struct SlowLog {
+    threshold: Duration,
+    max_len: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: VecDeque<SlowLogEntry>,
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct SlowLogEntry {
+    id: u64,
+    timestamp: u64,
+    duration: Duration,
+    command: &'static str,
+    args: Vec<Bytes>,
+    client: String,
+}
+
+impl SlowLog {
+    /// Creates the log, a zero threshold or max length disables it.
+    pub fn new(threshold: Duration, max_len: usize) -> Self {
+        SlowLog {
+            threshold,
+            max_len,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.threshold.is_zero() && self.max_len > 0
+    }
+
+    /// Records the command if it took longer than the threshold.
+    pub fn record(&self, command: &'static str, args: &[Bytes], client: &str, duration: Duration) {
+        if !self.is_enabled() || duration < self.threshold {
+            return;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let mut inner = self.inner.lock();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        if inner.entries.len() == self.max_len {
+            inner.entries.pop_back();
+        }
+        inner.entries.push_front(SlowLogEntry {
+            id,
+            timestamp,
+            duration,
+            command,
+            args: truncated_args(args),
+            client: client.to_string(),
+        });
+    }
+
+    /// Returns up to `count` entries, newest first.
+    pub fn get(&self, count: usize) -> Frame {
+        let inner = self.inner.lock();
+        Frame::Array(
+            inner
+                .entries
+                .iter()
+                .take(count)
+                .map(SlowLogEntry::to_frame)
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    pub fn reset(&self) {
+        self.inner.lock().entries.clear();
+    }
+}
+
+impl SlowLogEntry {
+    fn to_frame(&self) -> Frame {
+        Frame::Map(vec![
+            Frame::String(Bytes::from_static(b"id")),
+            Frame::Integer(self.id as i64),
+            Frame::String(Bytes::from_static(b"timestamp")),
+            Frame::Integer(self.timestamp as i64),
+            Frame::String(Bytes::from_static(b"duration_us")),
+            Frame::Integer(self.duration.as_micros() as i64),
+            Frame::String(Bytes::from_static(b"command")),
+            Frame::String(Bytes::from_static(self.command.as_bytes())),
+            Frame::String(Bytes::from_static(b"args")),
+            Frame::Array(self.args.iter().cloned().map(Frame::String).collect()),
+            Frame::String(Bytes::from_static(b"client")),
+            Frame::String(Bytes::from(self.client.clone())),
+        ])
+    }
+}
+
+// long arguments are cut short and arguments past the max are summed up in a last
+// argument
+fn truncated_args(args: &[Bytes]) -> Vec<Bytes> {
+    let mut truncated: Vec<Bytes> = args
+        .iter()
+        .take(MAX_ARGS)
+        .map(|arg| {
+            if arg.len() <= MAX_ARG_LEN {
+                return arg.clone();
+            }
+            let mut truncated = BytesMut::from(&arg[..MAX_ARG_LEN]);
+            truncated.extend_from_slice(
+                format!("... ({} more bytes)", arg.len() - MAX_ARG_LEN).as_bytes(),
+            );
+            truncated.freeze()
+        })
+        .collect();
+    if args.len() > MAX_ARGS {
+        truncated.push(Bytes::from(format!(
+            "... ({} more arguments)",
+            args.len() - MAX_ARGS
+        )));
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(args: &[&[u8]]) -> Vec<Bytes> {
+        args.iter().map(|arg| Bytes::copy_from_slice(arg)).collect()
+    }
+
+    #[test]
+    fn record_given_full_log_drops_oldest_entry() {
+        let slowlog = SlowLog::new(Duration::from_micros(100), 2);
+        let args = bytes(&[b"ks", b"key"]);
+        slowlog.record("get", &args, "127.0.0.1:5000", Duration::from_micros(50));
+        assert_eq!(slowlog.len(), 0);
+
+        for _ in 0..3 {
+            slowlog.record("get", &args, "127.0.0.1:5000", Duration::from_micros(150));
+        }
+        assert_eq!(slowlog.len(), 2);
+
+        let Frame::Array(entries) = slowlog.get(10) else {
+            panic!("expected an array");
+        };
+        assert_eq!(entries.len(), 2);
+        let Frame::Map(fields) = &entries[0] else {
+            panic!("expected a map");
+        };
+        assert_eq!(fields[1], Frame::Integer(2));
+
+        slowlog.reset();
+        assert_eq!(slowlog.len(), 0);
+    }
+
+    #[test]
+    fn truncated_args_given_large_command_truncates_args() {
+        let value = vec![b'a'; MAX_ARG_LEN + 10];
+        let args = truncated_args(&bytes(&[b"ks", &value]));
+        assert_eq!(args.len(), 2);
+        assert_eq!(args[1].len(), MAX_ARG_LEN + "... (10 more bytes)".len());
+
+        let many = vec![b"x".as_slice(); MAX_ARGS + 5];
+        let args = truncated_args(&bytes(&many));
+        assert_eq!(args.len(), MAX_ARGS + 1);
+        assert_eq!(args[MAX_ARGS], Bytes::from("... (5 more arguments)"));
+    }
+}
@@ -0,0 +1,264 @@
+use crate::db::Evictor;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Upper bounds in seconds of the command latency histogram buckets.
+pub const LATENCY_BUCKETS: [f64; 10] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.05, 0.1, 1.0,
+];
+
+/// Server wide counters reported by `INFO`. Counters are atomics so that recording
+/// them never contends with command execution.
+#[derive(Debug)]
+pub struct ServerStats {
+    started: Instant,
+    max_memory: u64,
+    commands_processed: AtomicU64,
+    ops_per_sec: AtomicU64,
+    used_memory: AtomicU64,
+    connections: AtomicU64,
+    rejected_connections: AtomicU64,
+    // time and number of commands processed at the last sample
+    last_sample: Mutex<(Instant, u64)>,
+    commands: RwLock<HashMap<&'static str, Arc<CommandStats>>>,
+}
+
+/// Number of calls and latency histogram of a command.
+#[derive(Debug, Default)]
+pub struct CommandStats {
+    calls: AtomicU64,
+    latency_sum_us: AtomicU64,
+    // calls per bucket, the last bucket counts calls slower than every bound
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+}
+
+/// Point in time view of a keyspace, reported by `INFO` and the metrics endpoint.
+#[derive(Debug)]
+pub struct KeyspaceSnapshot {
+    pub evictor: Evictor,
+    pub keys: u64,
+    pub expiring_keys: u64,
+    pub used_memory: u64,
+    pub max_memory: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub expired: u64,
+    pub evicted: u64,
+}
+
+/// Counters of a keyspace reported by `INFO`.
+#[derive(Debug, Default)]
+pub struct KeyspaceStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    expired: AtomicU64,
+    evicted: AtomicU64,
+}
+
+impl ServerStats {
+    pub fn new(max_memory: u64) -> Self {
+        let now = Instant::now();
+        ServerStats {
+            started: now,
+            max_memory,
+            commands_processed: AtomicU64::new(0),
+            ops_per_sec: AtomicU64::new(0),
+            used_memory: AtomicU64::new(0),
+            connections: AtomicU64::new(0),
+            rejected_connections: AtomicU64::new(0),
+            last_sample: Mutex::new((now, 0)),
+            commands: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Records a command run by a connection and how long it took to run.
+    pub fn record_command(&self, command: &'static str, latency: Duration) {
+        self.commands_processed.fetch_add(1, Ordering::Relaxed);
+        let maybe_stats = self.commands.read().get(command).cloned();
+        let stats = match maybe_stats {
+            Some(stats) => stats,
+            None => self.commands.write().entry(command).or_default().clone(),
+        };
+        stats.record(latency);
+    }
+
+    pub fn record_connection(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rejected_connection(&self) {
+        self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Updates the memory used by the server and the number of commands processed per
+    /// second since the previous sample, called periodically by the memory monitor.
+    pub fn sample(&self, used_memory: u64) {
+        self.used_memory.store(used_memory, Ordering::Relaxed);
+
+        let processed = self.commands_processed.load(Ordering::Relaxed);
+        let mut last_sample = self.last_sample.lock();
+        let (sampled_at, sampled_processed) = *last_sample;
+        let elapsed = sampled_at.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            let ops_per_sec = (processed - sampled_processed) as f64 / elapsed;
+            self.ops_per_sec
+                .store(ops_per_sec.round() as u64, Ordering::Relaxed);
+        }
+        *last_sample = (Instant::now(), processed);
+    }
+
+    pub fn uptime_secs(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    pub fn commands_processed(&self) -> u64 {
+        self.commands_processed.load(Ordering::Relaxed)
+    }
+
+    pub fn ops_per_sec(&self) -> u64 {
+        self.ops_per_sec.load(Ordering::Relaxed)
+    }
+
+    pub fn used_memory(&self) -> u64 {
+        self.used_memory.load(Ordering::Relaxed)
+    }
+
+    pub fn max_memory(&self) -> u64 {
+        self.max_memory
+    }
+
+    pub fn connections(&self) -> u64 {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn rejected_connections(&self) -> u64 {
+        self.rejected_connections.load(Ordering::Relaxed)
+    }
+
+    /// Returns the stats of every command run so far ordered by command name.
+    pub fn commands(&self) -> Vec<(&'static str, Arc<CommandStats>)> {
+        let mut commands: Vec<(&'static str, Arc<CommandStats>)> = self
+            .commands
+            .read()
+            .iter()
+            .map(|(name, stats)| (*name, stats.clone()))
+            .collect();
+        commands.sort_by_key(|(name, _)| *name);
+        commands
+    }
+}
+
+impl CommandStats {
+    fn record(&self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    pub fn latency_sum(&self) -> Duration {
+        Duration::from_micros(self.latency_sum_us.load(Ordering::Relaxed))
+    }
+
+    /// Returns the cumulative number of calls of each bucket in `LATENCY_BUCKETS`
+    /// followed by the total number of calls.
+    pub fn cumulative_buckets(&self) -> Vec<u64> {
+        let mut total = 0;
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                total += bucket.load(Ordering::Relaxed);
+                total
+            })
+            .collect()
+    }
+}
+
+impl KeyspaceStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_expired(&self) {
+        self.expired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_evicted(&self) {
+        self.evicted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn expired(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    pub fn evicted(&self) -> u64 {
+        self.evicted.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_given_processed_commands_computes_ops_per_sec() {
+        let stats = ServerStats::new(0);
+        for _ in 0..100 {
+            stats.record_command("get", Duration::from_micros(50));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        stats.sample(2048);
+
+        assert_eq!(stats.commands_processed(), 100);
+        assert_eq!(stats.used_memory(), 2048);
+        assert!(stats.ops_per_sec() > 0 && stats.ops_per_sec() <= 1000);
+    }
+
+    #[test]
+    fn record_command_given_latencies_fills_cumulative_buckets() {
+        let stats = ServerStats::new(0);
+        stats.record_command("set", Duration::from_micros(50));
+        stats.record_command("set", Duration::from_millis(3));
+        stats.record_command("set", Duration::from_secs(2));
+        stats.record_command("get", Duration::from_micros(50));
+
+        let commands = stats.commands();
+        assert_eq!(commands.len(), 2);
+        let (name, set) = &commands[1];
+        assert_eq!(*name, "set");
+        assert_eq!(set.calls(), 3);
+        assert_eq!(
+            set.cumulative_buckets(),
+            vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 3]
+        );
+        assert_eq!(set.latency_sum(), Duration::from_micros(2_003_050));
+    }
+}
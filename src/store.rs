@@ -0,0 +1,198 @@
+use crate::db::Value;
+use bytes::Bytes;
+use std::collections::{hash_map, HashMap};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Keys of a keyspace along with an estimate of the memory they use. The estimate is
+/// kept up to date on every write so that memory limits can be checked without
+/// scanning the keyspace.
+#[derive(Debug, Default)]
+pub struct Store {
+    entries: HashMap<Bytes, Value>,
+    memory_usage: usize,
+    // shared so that the number of keys with an expiry can be read without the lock
+    expiring: Arc<AtomicU64>,
+}
+
+/// Mutable access to a stored value, the memory used by the value is measured again
+/// once it is released if it was mutated.
+pub struct ValueMut<'a> {
+    value: &'a mut Value,
+    memory_usage: &'a mut usize,
+    // size of the value before its first mutation
+    before: Option<usize>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Estimated bytes used by the keys and values of the store.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// Number of keys with an expiry, kept up to date as keys are inserted and removed.
+    pub fn expiring(&self) -> Arc<AtomicU64> {
+        self.expiring.clone()
+    }
+
+    pub fn contains_key(&self, key: &Bytes) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn get(&self, key: &Bytes) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &Bytes) -> Option<ValueMut<'_>> {
+        let Store {
+            entries,
+            memory_usage,
+            ..
+        } = self;
+        entries.get_mut(key).map(|value| ValueMut {
+            value,
+            memory_usage,
+            before: None,
+        })
+    }
+
+    pub fn insert(&mut self, key: Bytes, value: Value) -> Option<Value> {
+        self.memory_usage += entry_size(&key, &value);
+        if value.expire_at().is_some() {
+            self.expiring.fetch_add(1, Ordering::Relaxed);
+        }
+        let previous = self.entries.insert(key.clone(), value);
+        if let Some(previous) = &previous {
+            self.forget(&key, previous);
+        }
+        previous
+    }
+
+    pub fn remove(&mut self, key: &Bytes) -> Option<Value> {
+        let value = self.entries.remove(key)?;
+        self.forget(key, &value);
+        Some(value)
+    }
+
+    /// Removes every key, the keys and their values are returned by the iterator.
+    pub fn drain(&mut self) -> hash_map::Drain<'_, Bytes, Value> {
+        self.memory_usage = 0;
+        self.expiring.store(0, Ordering::Relaxed);
+        self.entries.drain()
+    }
+
+    // takes a key that is no longer stored out of the accounting
+    fn forget(&mut self, key: &Bytes, value: &Value) {
+        self.memory_usage -= entry_size(key, value);
+        if value.expire_at().is_some() {
+            self.expiring.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn keys(&self) -> hash_map::Keys<'_, Bytes, Value> {
+        self.entries.keys()
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, Bytes, Value> {
+        self.entries.iter()
+    }
+}
+
+impl ValueMut<'_> {
+    /// Marks the value as accessed, this does not count as a mutation.
+    pub fn touch(&mut self) {
+        self.value.touch();
+    }
+}
+
+impl Deref for ValueMut<'_> {
+    type Target = Value;
+
+    fn deref(&self) -> &Value {
+        self.value
+    }
+}
+
+impl DerefMut for ValueMut<'_> {
+    fn deref_mut(&mut self) -> &mut Value {
+        if self.before.is_none() {
+            self.before = Some(self.value.memory_usage());
+        }
+        self.value
+    }
+}
+
+impl Drop for ValueMut<'_> {
+    fn drop(&mut self) {
+        if let Some(before) = self.before {
+            *self.memory_usage = *self.memory_usage - before + self.value.memory_usage();
+        }
+    }
+}
+
+/// Estimated bytes used by a key and its value in the store.
+pub fn entry_size(key: &Bytes, value: &Value) -> usize {
+    std::mem::size_of::<Bytes>() + key.len() + value.memory_usage()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Data;
+
+    #[test]
+    fn memory_usage_given_writes_tracks_entries() {
+        let mut store = Store::new();
+        let key = Bytes::from("key");
+        store.insert(
+            key.clone(),
+            Value::new(Data::String(Bytes::from("a")), None),
+        );
+        let small = store.memory_usage();
+        assert_eq!(small, entry_size(&key, store.get(&key).unwrap()));
+
+        store.insert(
+            key.clone(),
+            Value::new(Data::String(Bytes::from("abc")), None),
+        );
+        assert_eq!(store.memory_usage(), small + 2);
+
+        if let Some(mut val) = store.get_mut(&key) {
+            *val.data_mut() = Data::String(Bytes::from("abcdef"));
+        }
+        assert_eq!(store.memory_usage(), small + 5);
+
+        store.remove(&key);
+        assert_eq!(store.memory_usage(), 0);
+    }
+
+    #[test]
+    fn expiring_given_writes_counts_keys_with_expiry() {
+        let mut store = Store::new();
+        let expiring = store.expiring();
+        let value = |expire_at| Value::new(Data::String(Bytes::from("a")), expire_at);
+        store.insert(Bytes::from("a"), value(Some(1)));
+        store.insert(Bytes::from("b"), value(Some(1)));
+        store.insert(Bytes::from("c"), value(None));
+        assert_eq!(expiring.load(Ordering::Relaxed), 2);
+
+        store.insert(Bytes::from("a"), value(None));
+        store.remove(&Bytes::from("b"));
+        store.remove(&Bytes::from("c"));
+        assert_eq!(expiring.load(Ordering::Relaxed), 0);
+
+        store.insert(Bytes::from("d"), value(Some(1)));
+        assert_eq!(store.drain().count(), 2);
+        assert_eq!(expiring.load(Ordering::Relaxed), 0);
+        assert_eq!(store.memory_usage(), 0);
+    }
+}
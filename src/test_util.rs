@@ -0,0 +1,71 @@
+use crate::command::{self, Command};
+use crate::db::Db;
+use crate::frame::Frame;
+use crate::slowlog::SlowLog;
+use bytes::Bytes;
+use crossbeam::sync::WaitGroup;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// A db for tests, the shutdown and evict channels are kept open for as long as it is
+/// alive so that the keyspace tasks keep running instead of spinning on closed channels.
+pub struct TestDb {
+    db: Arc<Db>,
+    done_tx: broadcast::Sender<()>,
+    evict_tx: broadcast::Sender<()>,
+}
+
+impl TestDb {
+    pub fn new() -> Self {
+        let (done_tx, _) = broadcast::channel(1);
+        let (evict_tx, _) = broadcast::channel(1);
+        let db = Db::new(
+            done_tx.subscribe(),
+            WaitGroup::new(),
+            evict_tx.subscribe(),
+            0,
+            SlowLog::new(Duration::ZERO, 0),
+        );
+        TestDb {
+            db: Arc::new(db),
+            done_tx,
+            evict_tx,
+        }
+    }
+
+    pub fn arc(&self) -> Arc<Db> {
+        self.db.clone()
+    }
+
+    pub fn done(&self) -> broadcast::Receiver<()> {
+        self.done_tx.subscribe()
+    }
+
+    /// Signals the max memory evictors of every keyspace to evict a key.
+    pub fn evict(&self) {
+        self.evict_tx.send(()).unwrap();
+    }
+}
+
+impl Deref for TestDb {
+    type Target = Db;
+
+    fn deref(&self) -> &Db {
+        &self.db
+    }
+}
+
+/// Builds the frame a client sends for the command.
+pub fn frame(args: &[&str]) -> Frame {
+    Frame::Array(
+        args.iter()
+            .map(|arg| Frame::String(Bytes::copy_from_slice(arg.as_bytes())))
+            .collect(),
+    )
+}
+
+pub fn cmd(args: &[&str]) -> Command {
+    command::parse(frame(args)).unwrap()
+}
@@ -0,0 +1,197 @@
+use crate::config::ServerConfig;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{self, RootCertStore};
+use tokio_rustls::TlsAcceptor;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("failed to read '{0}', {1}")]
+    Read(String, io::Error),
+
+    #[error("no certificates found in '{0}'")]
+    NoCertificates(String),
+
+    #[error("no private key found in '{0}'")]
+    NoPrivateKey(String),
+
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+
+    #[error(transparent)]
+    Verifier(#[from] rustls::server::VerifierBuilderError),
+}
+
+/// Builds the acceptor used to wrap connections accepted on the tls port. Clients
+/// have to present a certificate signed by the CA when a CA file is configured.
+pub fn acceptor(cfg: &ServerConfig) -> Result<TlsAcceptor, TlsError> {
+    let cert_file = cfg.tls_cert_file().unwrap_or_default();
+    let key_file = cfg.tls_key_file().unwrap_or_default();
+    let certs = load_certs(cert_file)?;
+    let key = load_key(key_file)?;
+
+    let builder = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?;
+
+    let builder = match cfg.tls_ca_file() {
+        Some(ca_file) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_file)? {
+                roots.add(cert)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(
+                Arc::new(roots),
+                Arc::new(rustls::crypto::ring::default_provider()),
+            )
+            .build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let config = builder.with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let file = File::open(path).map_err(|e| TlsError::Read(path.to_string(), e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, io::Error>>()
+        .map_err(|e| TlsError::Read(path.to_string(), e))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(path.to_string()));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, TlsError> {
+    let file = File::open(path).map_err(|e| TlsError::Read(path.to_string(), e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| TlsError::Read(path.to_string(), e))?
+        .ok_or_else(|| TlsError::NoPrivateKey(path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::Connection;
+    use crate::frame::Frame;
+    use bytes::Bytes;
+    use rcgen::{BasicConstraints, CertificateParams, CertifiedKey, IsCa, KeyPair};
+    use std::path::PathBuf;
+    use tokio_rustls::rustls::pki_types::{PrivatePkcs8KeyDer, ServerName};
+    use tokio_rustls::TlsConnector;
+
+    // writes a file to a directory unique to the test and returns its path
+    fn write_file(test: &str, name: &str, contents: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("segment-tls-{}-{}", std::process::id(), test));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path: PathBuf = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn server_config(test: &str, server: &CertifiedKey, ca: Option<&str>) -> ServerConfig {
+        let cert_file = write_file(test, "server.crt", &server.cert.pem());
+        let key_file = write_file(test, "server.key", &server.key_pair.serialize_pem());
+        let mut config = format!(
+            "tls_port=0\ntls_cert_file={}\ntls_key_file={}\n",
+            cert_file, key_file
+        );
+        if let Some(ca) = ca {
+            config.push_str(&format!("tls_ca_file={}\n", write_file(test, "ca.crt", ca)));
+        }
+        let config_file = write_file(test, "segment.conf", &config);
+        ServerConfig::load_from_disk(&config_file).unwrap()
+    }
+
+    fn connector(server: &CertifiedKey, client: Option<&CertifiedKey>) -> TlsConnector {
+        let mut roots = RootCertStore::empty();
+        roots.add(server.cert.der().clone()).unwrap();
+        let builder = rustls::ClientConfig::builder_with_provider(Arc::new(
+            rustls::crypto::ring::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots);
+        let config = match client {
+            Some(client) => builder
+                .with_client_auth_cert(
+                    vec![client.cert.der().clone()],
+                    PrivatePkcs8KeyDer::from(client.key_pair.serialize_der()).into(),
+                )
+                .unwrap(),
+            None => builder.with_no_client_auth(),
+        };
+        TlsConnector::from(Arc::new(config))
+    }
+
+    fn ca_signed_client() -> (String, CertifiedKey) {
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = params.self_signed(&ca_key).unwrap();
+
+        let client_key = KeyPair::generate().unwrap();
+        let client = CertificateParams::new(vec!["client".to_string()])
+            .unwrap()
+            .signed_by(&client_key, &ca, &ca_key)
+            .unwrap();
+        (
+            ca.pem(),
+            CertifiedKey {
+                cert: client,
+                key_pair: client_key,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn acceptor_given_self_signed_certificate_exchanges_frames() {
+        let server = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let acceptor = acceptor(&server_config("plain", &server, None)).unwrap();
+        let connector = connector(&server, None);
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let (client_stream, server_stream) = tokio::join!(
+            connector.connect(ServerName::try_from("localhost").unwrap(), client_stream),
+            acceptor.accept(server_stream)
+        );
+        let mut client = Connection::new(client_stream.unwrap(), 4096);
+        let mut server = Connection::new(server_stream.unwrap(), 4096);
+
+        let frame = Frame::String(Bytes::from("ping"));
+        client.write_frame(&frame).await.unwrap();
+        assert_eq!(server.read_frame().await.unwrap(), Some(frame));
+    }
+
+    #[tokio::test]
+    async fn acceptor_given_ca_file_requires_client_certificate() {
+        let server = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let (ca, client) = ca_signed_client();
+        let acceptor = acceptor(&server_config("mutual", &server, Some(&ca))).unwrap();
+
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+        let (_, accepted) = tokio::join!(
+            connector(&server, None)
+                .connect(ServerName::try_from("localhost").unwrap(), client_stream),
+            acceptor.accept(server_stream)
+        );
+        assert!(accepted.is_err());
+
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+        let (connected, accepted) = tokio::join!(
+            connector(&server, Some(&client))
+                .connect(ServerName::try_from("localhost").unwrap(), client_stream),
+            acceptor.accept(server_stream)
+        );
+        assert!(connected.is_ok());
+        assert!(accepted.is_ok());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::frame::Frame;
+use crate::pubsub::Subscriber;
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackingMode {
+    /// Invalidates the keys read by the connection, every key is invalidated only
+    /// once and has to be read again to be tracked again.
+    Default,
+    /// Invalidates every key starting with one of the prefixes, or every key if there
+    /// are none. Uses no memory per key at the cost of more invalidations.
+    Broadcast(Vec<Bytes>),
+}
+
+/// Remembers which connections cache which keys so that they can be told when
+/// a key changes. Shared by all keyspaces of a db.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    keys: Mutex<HashMap<(Bytes, Bytes), HashMap<u64, Subscriber>>>,
+    broadcasts: RwLock<HashMap<u64, (Subscriber, Vec<Bytes>)>>,
+    clients: Mutex<HashSet<u64>>,
+    // number of clients, checked before taking any lock when invalidating
+    active: AtomicUsize,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enable(&self, subscriber: &Subscriber, mode: &TrackingMode) {
+        self.disable(subscriber);
+        if let TrackingMode::Broadcast(prefixes) = mode {
+            let mut handle = self.broadcasts.write();
+            handle.insert(subscriber.id(), (subscriber.clone(), prefixes.clone()));
+        }
+        let mut clients = self.clients.lock();
+        clients.insert(subscriber.id());
+        self.active.store(clients.len(), Ordering::Relaxed);
+    }
+
+    /// Forgets everything tracked for the subscriber, does nothing if tracking
+    /// was not enabled.
+    pub fn disable(&self, subscriber: &Subscriber) {
+        let mut clients = self.clients.lock();
+        if !clients.remove(&subscriber.id()) {
+            return;
+        }
+        self.active.store(clients.len(), Ordering::Relaxed);
+        self.broadcasts.write().remove(&subscriber.id());
+        self.keys.lock().retain(|_, readers| {
+            readers.remove(&subscriber.id());
+            !readers.is_empty()
+        });
+    }
+
+    pub fn track(&self, keyspace: &Bytes, key: &Bytes, subscriber: &Subscriber) {
+        let mut handle = self.keys.lock();
+        handle
+            .entry((keyspace.clone(), key.clone()))
+            .or_default()
+            .insert(subscriber.id(), subscriber.clone());
+    }
+
+    pub fn invalidate(&self, keyspace: &Bytes, key: &Bytes) {
+        if self.active.load(Ordering::Relaxed) == 0 {
+            return;
+        }
+
+        let frame = || {
+            Frame::Array(vec![
+                Frame::String(Bytes::from_static(b"invalidate")),
+                Frame::String(keyspace.clone()),
+                Frame::String(key.clone()),
+            ])
+        };
+
+        let readers = self.keys.lock().remove(&(keyspace.clone(), key.clone()));
+        for subscriber in readers
+            .into_iter()
+            .flat_map(|readers| readers.into_values())
+        {
+            subscriber.push(frame());
+        }
+
+        for (subscriber, prefixes) in self.broadcasts.read().values() {
+            if prefixes.is_empty() || prefixes.iter().any(|prefix| key.starts_with(prefix)) {
+                subscriber.push(frame());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalidation(keyspace: &str, key: &str) -> Frame {
+        Frame::Array(vec![
+            Frame::String(Bytes::from("invalidate")),
+            Frame::String(Bytes::from(keyspace.to_string())),
+            Frame::String(Bytes::from(key.to_string())),
+        ])
+    }
+
+    #[test]
+    fn invalidate_given_tracked_key_pushes_invalidation_once() {
+        let tracker = Tracker::new();
+        let (subscriber, mut rx) = Subscriber::new(8);
+        tracker.enable(&subscriber, &TrackingMode::Default);
+        tracker.track(&Bytes::from("ks"), &Bytes::from("foo"), &subscriber);
+
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("bar"));
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("foo"));
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("foo"));
+
+        assert_eq!(rx.try_recv().unwrap(), invalidation("ks", "foo"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn invalidate_given_broadcast_prefix_pushes_matching_keys() {
+        let tracker = Tracker::new();
+        let (subscriber, mut rx) = Subscriber::new(8);
+        let mode = TrackingMode::Broadcast(vec![Bytes::from("user:")]);
+        tracker.enable(&subscriber, &mode);
+
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("order:1"));
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("user:1"));
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("user:1"));
+
+        assert_eq!(rx.try_recv().unwrap(), invalidation("ks", "user:1"));
+        assert_eq!(rx.try_recv().unwrap(), invalidation("ks", "user:1"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn invalidate_given_disabled_tracking_pushes_nothing() {
+        let tracker = Tracker::new();
+        let (subscriber, mut rx) = Subscriber::new(8);
+        tracker.enable(&subscriber, &TrackingMode::Default);
+        tracker.track(&Bytes::from("ks"), &Bytes::from("foo"), &subscriber);
+        tracker.disable(&subscriber);
+
+        tracker.invalidate(&Bytes::from("ks"), &Bytes::from("foo"));
+
+        assert!(rx.try_recv().is_err());
+        assert_eq!(tracker.active.load(Ordering::Relaxed), 0);
+    }
+}